@@ -0,0 +1,36 @@
+// Keeps the app playable without a connection: every page, script and asset fetched while online is cached,
+// and served from the cache when the network fails. Server functions are never cached; when they fail the
+// app draws its word pools from the cached word index instead.
+const CACHE = 'vamos-ler-v3';
+const PRECACHE = ['/', '/word-index/pt.json', '/assets/main.css', '/assets/manifest.json', '/assets/icon.svg'];
+
+self.addEventListener('install', (event) => {
+    event.waitUntil(caches.open(CACHE).then((cache) => cache.addAll(PRECACHE)).then(() => self.skipWaiting()));
+});
+
+self.addEventListener('activate', (event) => {
+    event.waitUntil(
+        caches.keys()
+            .then((keys) => Promise.all(keys.filter((key) => key !== CACHE).map((key) => caches.delete(key))))
+            .then(() => self.clients.claim())
+    );
+});
+
+self.addEventListener('fetch', (event) => {
+    const url = new URL(event.request.url);
+    if (event.request.method !== 'GET' || url.origin !== self.location.origin || url.pathname.startsWith('/api/')) {
+        return;
+    }
+
+    event.respondWith(
+        fetch(event.request)
+            .then((response) => {
+                if (response.ok) {
+                    const copy = response.clone();
+                    caches.open(CACHE).then((cache) => cache.put(event.request, copy));
+                }
+                return response;
+            })
+            .catch(() => caches.match(event.request).then((cached) => cached || caches.match('/')))
+    );
+});
@@ -1,13 +1,31 @@
 use leptos::*;
+use leptos::leptos_dom::helpers::IntervalHandle;
 use leptos_meta::*;
 use leptos_router::*;
-use leptos_use::storage::{use_local_storage, JsonCodec};
+use leptos_use::storage::{use_local_storage, use_local_storage_with_options, JsonCodec, UseStorageOptions};
 use serde::{Serialize, Deserialize};
+use std::time::Duration;
 
+use crate::backup::BackupPage;
+use crate::curriculum::Lesson;
+use crate::dictionary::{DictionaryProvider, DictionaryWord};
+use crate::i18n::{self, LocalePicker, Msg};
+use crate::languages::{self, Language};
+use crate::profiles::{self, ProfilePicker};
+use crate::recognition::ChoiceBoard;
+use crate::review::{self, ReviewCard};
+use crate::stats::StatsPage;
+use crate::progress;
+use crate::run_results::RunResults;
+use crate::scores::{self, RunRecord};
 use crate::setup_run::SetupRun;
-use crate::lexicanum;
+use crate::speech;
+use crate::spelling::SpellingBoard;
+use crate::lexicanum::{self, PoolWord, WordPoolError};
+use crate::outcomes::{self, Outcome, WordOutcome};
+use crate::word_index;
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, Default)]
 pub enum Difficulty {
     Easiest,
     #[default]
@@ -17,24 +35,157 @@ pub enum Difficulty {
     Hardest,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+impl Difficulty {
+    pub const ALL: [Difficulty; 5] = [Difficulty::Easiest, Difficulty::Easy, Difficulty::Medium, Difficulty::Hard, Difficulty::Hardest];
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum ReadingMode {
+    #[default]
+    Words,
+    Sentences,
+    /// Hear (or see the picture of) a word from the pool and spell it on the play keyboard.
+    Spelling,
+    /// Hear (or see the picture of) a word and pick it among others that look like it.
+    Recognition,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum GameMode {
+    /// Read through a fixed number of words.
+    #[default]
+    Classic,
+    /// Read as many words as possible until the time runs out.
+    TimeAttack,
+}
+
+/// How long a time attack lasts until another limit is picked.
+pub const DEFAULT_TIME_LIMIT_SECS: u32 = 60;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RunPhase {
+    Setup,
+    Reading,
+    Finished,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct RunSettings {
+    /// The profile whose settings these are, so nested components can namespace what they store.
+    pub profile_id: u64,
     pub num_words: RwSignal<usize>,
-    pub word_pool: RwSignal<Vec::<String>>, 
+    pub word_pool: RwSignal<Vec::<PoolWord>>, 
     pub allowed_chars: Signal<String>,
     pub set_allowed_chars: WriteSignal<String>,
     pub all_words: Signal<bool>,
     pub set_all_words: WriteSignal<bool>,
     pub difficulty: Signal<Difficulty>,
     pub set_difficulty: WriteSignal<Difficulty>,
+    pub reading_mode: Signal<ReadingMode>,
+    pub set_reading_mode: WriteSignal<ReadingMode>,
+    pub game_mode: Signal<GameMode>,
+    pub set_game_mode: WriteSignal<GameMode>,
+    pub time_limit_secs: Signal<u32>,
+    pub set_time_limit_secs: WriteSignal<u32>,
+    pub show_syllables: Signal<bool>,
+    pub set_show_syllables: WriteSignal<bool>,
+    /// The lesson last picked in the setup screen. It stays picked after the letters are changed by hand, but is no longer played.
+    pub lesson: Signal<Option<Lesson>>,
+    pub set_lesson: WriteSignal<Option<Lesson>>,
+    /// Words the child needed help with, coming back in later runs.
+    pub review_deck: Signal<Vec<ReviewCard>>,
+    pub set_review_deck: WriteSignal<Vec<ReviewCard>>,
+    /// Code of the language being read. Empty until a language is picked, which plays the default one.
+    pub language: Signal<String>,
+    pub set_language: WriteSignal<String>,
+    pub listen_after_trying: Signal<bool>,
+    pub set_listen_after_trying: WriteSignal<bool>,
+    /// Where tapping the word being read looks it up.
+    pub dictionary: Signal<DictionaryProvider>,
+    pub set_dictionary: WriteSignal<DictionaryProvider>,
+    /// Words with a picture are preferred, and shown with it.
+    pub with_pictures: Signal<bool>,
+    pub set_with_pictures: WriteSignal<bool>,
+    pub picture_after_reading: Signal<bool>,
+    pub set_picture_after_reading: WriteSignal<bool>,
+}
+
+/// What to ask the server for when putting a pool together.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PoolOptions {
+    /// Code of the language to draw words from.
+    pub language: String,
+    /// `None` when every word is allowed.
+    pub allowed_chars: Option<String>,
+    pub num_words: usize,
+    pub difficulty: Difficulty,
+    pub reading_mode: ReadingMode,
+    /// Letters and digraphs of the lesson being played, preferred in word pools.
+    pub focus: Vec<String>,
+    /// Words due for review, most urgent first.
+    pub review: Vec<String>,
+    /// Prefer words that have a picture, and bring their pictures along.
+    pub with_pictures: bool,
+}
+
+impl RunSettings {
+    pub fn current_language(&self) -> &'static Language {
+        languages::language(&self.language.get())
+    }
+
+    /// The picked lesson, as long as the letters switched on are still exactly the lesson's.
+    pub fn active_lesson(&self) -> Option<Lesson> {
+        match self.all_words.get() {
+            true => None,
+            false => self.lesson.get().filter(|lesson| lesson.allowed_chars(self.current_language()) == self.allowed_chars.get()),
+        }
+    }
+
+    /// The options for [`fetch_pool`] matching the current settings.
+    pub fn pool_options(&self) -> PoolOptions {
+        let filter = match self.all_words.get_untracked() {
+            true => None,
+            false=> Some(self.allowed_chars.get_untracked()),
+        };
+        let reading_mode = self.reading_mode.get_untracked();
+        PoolOptions {
+            language: untrack(|| self.current_language()).code.to_string(),
+            allowed_chars: filter,
+            num_words: self.num_words.get_untracked(),
+            difficulty: self.difficulty.get_untracked(),
+            focus: untrack(|| self.active_lesson()).map(|lesson| lesson.focus()).unwrap_or_default(),
+            review: self.review_deck.with_untracked(|deck| review::due(deck, &reading_mode, untrack(|| self.current_language()).code, scores::today())),
+            with_pictures: self.with_pictures.get_untracked(),
+            reading_mode,
+        }
+    }
+}
+
+/// Asks the server for a new pool of words or sentences, depending on the reading mode.
+/// Words with one of the `focus` letters or digraphs are preferred, sentences ignore it.
+/// Without a server the pool is drawn in the browser, from the word index.
+pub async fn fetch_pool(options: PoolOptions) -> Result<Vec<PoolWord>, ServerFnError> {
+    let from_server = {
+        let options = options.clone();
+        async move {
+            match options.reading_mode {
+                ReadingMode::Words | ReadingMode::Spelling => lexicanum::get_word_pool(options.language, options.allowed_chars, options.num_words, options.difficulty, options.focus, options.review, options.with_pictures).await,
+                ReadingMode::Sentences => lexicanum::get_sentence_pool(options.language, options.allowed_chars, options.num_words, options.difficulty, options.review).await,
+                ReadingMode::Recognition => lexicanum::get_questions(options.language, options.allowed_chars, options.num_words, options.difficulty, options.focus, options.review, options.with_pictures).await,
+            }
+        }
+    };
+    word_index::with_local_fallback(from_server, word_index::local_pool(options)).await
 }
 
 #[component]
 pub fn App() -> impl IntoView {
     // Provides context that manages stylesheets, titles, meta tags, etc.
     provide_meta_context();
+    let locale = i18n::use_locale().locale;
 
     view! {
+        <Html lang=move || locale.get().code().to_string() />
         <Script async_ = "true" src="https://www.googletagmanager.com/gtag/js?id=G-X6E71G2155"></Script>
         <Script>{r#"
             window.dataLayer = window.dataLayer || [];
@@ -51,14 +202,27 @@ pub fn App() -> impl IntoView {
         // id=leptos means cargo-leptos will hot-reload this stylesheet
         <Stylesheet id="leptos" href="assets/main.css"/>
 
+        // installable, and playable offline once the service worker has cached the app and the word index
+        <Link rel="manifest" href="/assets/manifest.json"/>
+        <Meta name="theme-color" content="#1D3557"/>
+        <Script>{r#"
+            if ('serviceWorker' in navigator) {
+                navigator.serviceWorker.register('/sw.js');
+            }
+            "#}
+        </Script>
+
         // sets the document title
-        <Title text="Vamos Ler!"/>
+        <Title text=move || Msg::AppTitle.text(locale.get()).to_string()/>
 
         // content for this welcome page
         <Router>
             <main>
                 <Routes>
                     <Route path="" view=HomePage/>
+                    <Route path="/profiles" view=ProfilePicker/>
+                    <Route path="/stats" view=StatsPage/>
+                    <Route path="/backup" view=BackupPage/>
                     <Route path="/*any" view=NotFound/>
                 </Routes>
             </main>
@@ -66,7 +230,7 @@ pub fn App() -> impl IntoView {
     }
 }
 
-fn select_word(existing_words: RwSignal<Vec<String>>) -> Option<String> {
+fn select_word(existing_words: RwSignal<Vec<PoolWord>>) -> Option<PoolWord> {
         existing_words.try_update( |words| {
             logging::log!("attempting a word");
             match words.len() {
@@ -84,64 +248,395 @@ fn select_word(existing_words: RwSignal<Vec<String>>) -> Option<String> {
 /// Renders the home page of your application.
 #[component]
 fn HomePage() -> impl IntoView {
-    let (allowed_chars, set_allowed_chars, _) = use_local_storage::<String, JsonCodec>("allowed_chars");
-    let (all_words, set_all_words, _) = use_local_storage::<bool, JsonCodec>("all_words");
-    let (difficulty, set_difficulty, _) = use_local_storage::<Difficulty, JsonCodec>("difficulty");
+    let locale = i18n::use_locale().locale;
+    let profiles::StoredProfiles { profiles, current_profile, .. } = profiles::use_profiles();
+    let profile_name = move || profiles.with(|profiles| profiles.iter().find(|p| Some(p.id) == current_profile.get()).map(|p| p.name.clone()));
+    // nobody picked yet (or the profile got deleted): go pick someone before reading
+    let navigate = use_navigate();
+    create_effect(move |_| {
+        if profile_name().is_none() {
+            navigate("/profiles", Default::default());
+        }
+    });
+
+    let profile_id = current_profile.get_untracked().unwrap_or_default();
+    let (allowed_chars, set_allowed_chars, _) = use_local_storage::<String, JsonCodec>(profiles::storage_key(profile_id, "allowed_chars"));
+    let (all_words, set_all_words, _) = use_local_storage::<bool, JsonCodec>(profiles::storage_key(profile_id, "all_words"));
+    let (difficulty, set_difficulty, _) = use_local_storage::<Difficulty, JsonCodec>(profiles::storage_key(profile_id, "difficulty"));
+    let (reading_mode, set_reading_mode, _) = use_local_storage::<ReadingMode, JsonCodec>(profiles::storage_key(profile_id, "reading_mode"));
+    let (game_mode, set_game_mode, _) = use_local_storage::<GameMode, JsonCodec>(profiles::storage_key(profile_id, "game_mode"));
+    let (run_history, set_run_history, _) = use_local_storage::<Vec<RunRecord>, JsonCodec>(profiles::storage_key(profile_id, "run_history"));
+    let (time_limit_secs, set_time_limit_secs, _) = use_local_storage_with_options::<u32, JsonCodec>(
+        profiles::storage_key(profile_id, "time_limit_secs"),
+        UseStorageOptions::default().initial_value(DEFAULT_TIME_LIMIT_SECS),
+    );
+    let (show_syllables, set_show_syllables, _) = use_local_storage::<bool, JsonCodec>(profiles::storage_key(profile_id, "show_syllables"));
+    let (lesson, set_lesson, _) = use_local_storage::<Option<Lesson>, JsonCodec>(profiles::storage_key(profile_id, "lesson"));
+    let (_, set_word_outcomes, _) = use_local_storage::<Vec<WordOutcome>, JsonCodec>(profiles::storage_key(profile_id, "word_outcomes"));
+    let (review_deck, set_review_deck, _) = use_local_storage::<Vec<ReviewCard>, JsonCodec>(profiles::storage_key(profile_id, "review_deck"));
+    let (language, set_language, _) = use_local_storage::<String, JsonCodec>(profiles::storage_key(profile_id, "language"));
+    let (listen_after_trying, set_listen_after_trying, _) = use_local_storage::<bool, JsonCodec>(profiles::storage_key(profile_id, "listen_after_trying"));
+    let (dictionary, set_dictionary, _) = use_local_storage::<DictionaryProvider, JsonCodec>(profiles::storage_key(profile_id, "dictionary"));
+    let (with_pictures, set_with_pictures, _) = use_local_storage::<bool, JsonCodec>(profiles::storage_key(profile_id, "with_pictures"));
+    let (picture_after_reading, set_picture_after_reading, _) = use_local_storage::<bool, JsonCodec>(profiles::storage_key(profile_id, "picture_after_reading"));
 
     // Creates a reactive value to update the button
     let settings = RunSettings {
+        profile_id,
         num_words: create_rw_signal(10),
-        word_pool: create_rw_signal(Vec::<String>::new()),
+        word_pool: create_rw_signal(Vec::<PoolWord>::new()),
         allowed_chars: allowed_chars,
         set_allowed_chars: set_allowed_chars,
         all_words: all_words,
         set_all_words: set_all_words,
         difficulty: difficulty,
         set_difficulty: set_difficulty,
+        reading_mode,
+        set_reading_mode,
+        game_mode,
+        set_game_mode,
+        time_limit_secs,
+        set_time_limit_secs,
+        show_syllables,
+        set_show_syllables,
+        lesson,
+        set_lesson,
+        review_deck,
+        set_review_deck,
+        language,
+        set_language,
+        listen_after_trying,
+        set_listen_after_trying,
+        dictionary,
+        set_dictionary,
+        with_pictures,
+        set_with_pictures,
+        picture_after_reading,
+        set_picture_after_reading,
     };
     
-    let is_reading = create_rw_signal(false);
-    let (word, set_word) = create_signal("".to_string());
+    // runs played on other devices show up in the history, and the ones only played here go to the server
+    if current_profile.get_untracked().is_some() {
+        let stored_runs = create_local_resource(|| (), move |_| async move { progress::load_stored_runs(profile_id).await });
+        create_effect(move |_| match stored_runs.get() {
+            Some(Ok(stored)) => {
+                let mut merged = run_history.get_untracked();
+                progress::merge_runs(&mut merged, stored);
+                set_run_history.set(merged.clone());
+                sync_runs(profile_id, merged);
+            },
+            Some(Err(e)) => progress::log_sync_error("runs", &e),
+            None => {},
+        });
+    }
+
+    let phase = create_rw_signal(RunPhase::Setup);
+    let (word, set_word) = create_signal(PoolWord::new(languages::DEFAULT_LANGUAGE, ""));
     let remaining_words = move || settings.word_pool.with(|words| words.len());
+    let words_read = create_rw_signal(0usize);
+    let seen_words = store_value(Vec::<String>::new());
+    let time_left = create_rw_signal(0u32);
+    let timer = store_value(None::<IntervalHandle>);
+    let started_at = store_value(0f64);
+    let word_shown_at = store_value(0f64);
+    // whether the child said they tried reading the current word before listening to it
+    let tried = create_rw_signal(false);
+    // whether the picture of the current word was uncovered, once the child has read it
+    let revealed = create_rw_signal(false);
+    let run_outcomes = store_value(Vec::<WordOutcome>::new());
+    let last_needed_help = create_rw_signal(Vec::<String>::new());
+    let last_run = create_rw_signal(None::<(RunRecord, bool)>);
+
+    let refill_pool = create_action(move |options: &PoolOptions| {
+        let cloned_options = options.clone();
+        logging::log!("word pool ran dry, calling server for more words");
+        async move { fetch_pool(cloned_options).await }
+    });
+
+    let finish_run = move || {
+        if let Some(handle) = timer.get_value() {
+            handle.clear();
+        }
+        timer.set_value(None);
+        settings.word_pool.set(vec![]);
+
+        if words_read.get_untracked() == 0 {
+            phase.set(RunPhase::Setup);
+            return;
+        }
+
+        let duration_secs = match settings.game_mode.get_untracked() {
+            GameMode::Classic => ((js_sys::Date::now() - started_at.get_value()) / 1000.0).round().max(1.0) as u32,
+            GameMode::TimeAttack => settings.time_limit_secs.get_untracked() - time_left.get_untracked(),
+        };
+        let run = RunRecord {
+            game_mode: settings.game_mode.get_untracked(),
+            reading_mode: settings.reading_mode.get_untracked(),
+            difficulty: settings.difficulty.get_untracked(),
+            allowed_chars: settings.pool_options().allowed_chars,
+            words_read: words_read.get_untracked(),
+            duration_secs,
+            day: scores::today(),
+            finished_at: js_sys::Date::now(),
+            language: untrack(|| settings.current_language()).code.to_string(),
+        };
+        let new_record = run_history.with_untracked(|history| scores::is_high_score(history, &run));
+        set_run_history.update(|history| history.push(run.clone()));
+        last_needed_help.set(run_outcomes.with_value(|o| outcomes::needed_help(o)));
+        sync_runs(settings.profile_id, vec![run.clone()]);
+        last_run.set(Some((run, new_record)));
+        phase.set(RunPhase::Finished);
+    };
 
     let get_new_word = move || {
         
         match select_word(settings.word_pool) {
             Some(w) => {
+                seen_words.update_value(|seen| seen.push(w.text.clone()));
+                word_shown_at.set_value(js_sys::Date::now());
+                tried.set(false);
+                revealed.set(false);
+                if matches!(settings.reading_mode.get_untracked(), ReadingMode::Spelling | ReadingMode::Recognition) {
+                    speech::speak(settings.current_language(), &w.text);
+                }
                 set_word(w)
             },
-            None => is_reading.set(false)
+            None => match settings.game_mode.get_untracked() {
+                GameMode::Classic => finish_run(),
+                GameMode::TimeAttack => refill_pool.dispatch(settings.pool_options()),
+            }
         }
     };
 
-    let click_new_word = move |_| {
+    // a pool that cannot be refilled ends the run, and the setup (or results) screen says why
+    let refill_error = create_rw_signal(None::<ServerFnError>);
+    create_effect(move |_| {
+        let Some(result) = refill_pool.value().get() else {
+            return;
+        };
+        if phase.get_untracked() != RunPhase::Reading {
+            return;
+        }
+        match result {
+            Ok(word_pool) if !word_pool.is_empty() => {
+                let unseen: Vec<PoolWord> = seen_words.with_value(|seen| word_pool.iter().filter(|w| !seen.contains(&w.text)).cloned().collect());
+                // small letter sets can run out of new words before the time does, so repeat words rather than stall
+                settings.word_pool.set(if unseen.is_empty() { word_pool } else { unseen });
+                get_new_word();
+            },
+            Ok(_) => {
+                refill_error.set(Some(WordPoolError::NoMatchingWords.into()));
+                finish_run();
+            },
+            Err(e) => {
+                refill_error.set(Some(e));
+                finish_run();
+            },
+        }
+    });
+    let refill_error_message = move || refill_error.get().map(|e| match WordPoolError::from_server_fn_error(&e) {
+        Some(pool_error) => pool_error.message(locale.get()),
+        None => Msg::CouldNotFetchWords.text(locale.get()),
+    });
+
+    let answer_word = move |outcome: Outcome| {
+        let now = js_sys::Date::now();
+        let word_outcome = WordOutcome {
+            text: word.with_untracked(|w| w.text.clone()),
+            reading_mode: settings.reading_mode.get_untracked(),
+            difficulty: settings.difficulty.get_untracked(),
+            outcome,
+            duration_ms: (now - word_shown_at.get_value()).max(0.0) as u32,
+            at: now,
+            language: untrack(|| settings.current_language()).code.to_string(),
+        };
+        run_outcomes.update_value(|o| o.push(word_outcome.clone()));
+        set_review_deck.update(|deck| review::schedule(deck, &word_outcome.text, &word_outcome.reading_mode, &word_outcome.language, outcome, scores::today()));
+        set_word_outcomes.update(|history| outcomes::record(history, word_outcome));
+
+        words_read.update(|n| *n += word.with_untracked(|w| w.text.split_whitespace().count()));
         get_new_word();
     };
+    let listen_view = move || match settings.listen_after_trying.get() && !tried.get() {
+        true => view! {
+            <div class="listen-button" on:click=move |_| tried.set(true)>{move || Msg::TriedIt.text(locale.get())}</div>
+        },
+        false => view! {
+            <div class="listen-button" on:click=move |_| speech::speak(settings.current_language(), &word.with_untracked(|w| w.text.clone()))>"🔊 "{move || Msg::Listen.text(locale.get())}</div>
+        },
+    };
+    let picture_view = move || {
+        let src = word.with(|w| w.picture.clone()).filter(|_| settings.with_pictures.get())?;
+        Some(match settings.picture_after_reading.get() && !revealed.get() {
+            true => view! {
+                <div class="reveal-picture-button" on:click=move |_| revealed.set(true)>{move || Msg::ShowPicture.text(locale.get())}</div>
+            }.into_view(),
+            false => view! {
+                <img class="word-picture" src=src alt=move || word.with(|w| w.text.clone()) />
+            }.into_view(),
+        })
+    };
+    let outcome_buttons = move || view! {
+        <div class="outcome-buttons">
+            <div class="next-word-button" on:click=move |_| answer_word(Outcome::Read)>{move || Msg::ReadAlone.text(locale.get())}</div>
+            <div class="needed-help-button" on:click=move |_| answer_word(Outcome::NeededHelp)>{move || Msg::NeededHelp.text(locale.get())}</div>
+        </div>
+    };
 
 
-    let start_reading= move |_| {
-        is_reading.set(true);
+    let start_reading = Callback::new(move |_| {
+        refill_error.set(None);
+        words_read.set(0);
+        seen_words.set_value(vec![]);
+        run_outcomes.set_value(vec![]);
+        started_at.set_value(js_sys::Date::now());
+        phase.set(RunPhase::Reading);
+        if settings.game_mode.get_untracked() == GameMode::TimeAttack {
+            time_left.set(settings.time_limit_secs.get_untracked());
+            let handle = set_interval_with_handle(move || {
+                time_left.update(|t| *t = t.saturating_sub(1));
+                if time_left.get_untracked() == 0 {
+                    finish_run();
+                }
+            }, Duration::from_secs(1));
+            timer.set_value(handle.ok());
+        }
         get_new_word();
+    });
+
+    let remaining_view = move || match settings.game_mode.get() {
+        GameMode::Classic => view! {
+            <div class="remaining-words"><span>{move || Msg::Left.text(locale.get())}</span><span style="font-weight: bold;">{remaining_words}</span><span>{move || match settings.reading_mode.get() { ReadingMode::Words | ReadingMode::Spelling | ReadingMode::Recognition => Msg::WordsLeft, ReadingMode::Sentences => Msg::SentencesLeft }.text(locale.get())}</span></div>
+        }.into_view(),
+        GameMode::TimeAttack => view! {
+            <div class="remaining-words"><span>{move || Msg::Left.text(locale.get())}</span><span style="font-weight: bold;">{time_left}</span><span>{move || Msg::SecondsLeftReadSoFar.text(locale.get())}</span><span style="font-weight: bold;">{words_read}</span><span>{move || Msg::WordsRead.text(locale.get())}</span></div>
+        }.into_view(),
     };
-        
 
     view! {
+        <div class="setup-error" style:display=move || if refill_error_message().is_some() { "block" } else { "none" }>{refill_error_message}</div>
         { move || 
-            match is_reading() {
-                true => view! { 
-                    <div class="active-word"><a target="window" href={move || format!("https://dicionario.priberam.org/{}",word())}>{word}</a></div>
-                    <div class="remaining-words"><span>"Faltam "</span><span style="font-weight: bold;">{remaining_words}</span><span>" palavras!"</span></div>
-                    <div class="next-word-button" on:click=click_new_word>"Outra Palavra!"</div>
-                }.into_view(),
-                false => view! {
-                    <SetupRun settings=settings.clone() onready=start_reading />
+            match phase.get() {
+                RunPhase::Reading => match settings.reading_mode.get() {
+                    ReadingMode::Words => view! { 
+                        <div class="active-word">{move || {
+                            // read here rather than inside the children, which do not track what they read
+                            let shown = word_view(word(), settings.show_syllables.get());
+                            view! { <DictionaryWord word=word().text language=settings.current_language() provider=settings.dictionary.get()>{shown}</DictionaryWord> }
+                        }}</div>
+                        {picture_view}
+                        {listen_view}
+                        {remaining_view}
+                        {outcome_buttons}
+                    }.into_view(),
+                    ReadingMode::Spelling => view! {
+                        {move || word.with(|w| w.picture.clone()).map(|src| view! { <img class="word-picture" src=src alt="" /> })}
+                        <div class="listen-button" on:click=move |_| speech::speak(settings.current_language(), &word.with_untracked(|w| w.text.clone()))>"🔊 "{move || Msg::Listen.text(locale.get())}</div>
+                        {move || view! {
+                            <SpellingBoard word=word().text language=settings.current_language() allowed_chars=settings.pool_options().allowed_chars ondone=Callback::new(answer_word) />
+                        }}
+                        {remaining_view}
+                    }.into_view(),
+                    ReadingMode::Recognition => view! {
+                        {move || word.with(|w| w.picture.clone()).map(|src| view! { <img class="word-picture" src=src alt="" /> })}
+                        <div class="listen-button" on:click=move |_| speech::speak(settings.current_language(), &word.with_untracked(|w| w.text.clone()))>"🔊 "{move || Msg::Listen.text(locale.get())}</div>
+                        {move || view! {
+                            <ChoiceBoard word=word().text choices=word().choices ondone=Callback::new(answer_word) />
+                        }}
+                        {remaining_view}
+                    }.into_view(),
+                    ReadingMode::Sentences => view! {
+                        <div class="active-sentence">{move || match settings.show_syllables.get() && !word().syllables.is_empty() {
+                            true => syllables_view(word().syllables).into_view(),
+                            false => sentence_view(word().text, settings.current_language(), settings.dictionary.get()).into_view(),
+                        }}</div>
+                        {listen_view}
+                        {remaining_view}
+                        {outcome_buttons}
+                    }.into_view(),
+                },
+                RunPhase::Finished => match last_run.get() {
+                    Some((run, new_record)) => view! {
+                        <RunResults run=run new_record=new_record history=run_history needed_help=last_needed_help.get_untracked() ondone=move |_| phase.set(RunPhase::Setup) />
+                    }.into_view(),
+                    None => ().into_view(),
+                },
+                RunPhase::Setup => view! {
+                    <div class="current-profile">
+                        <span>{move || Msg::Reading.text(locale.get())}</span><span style="font-weight: bold;">{profile_name}</span>" "
+                        <A href="/profiles">{move || Msg::SwitchProfile.text(locale.get())}</A>" "
+                        <A href="/stats">{move || Msg::Progress.text(locale.get())}</A>" "
+                        <A href="/backup">{move || Msg::Backups.text(locale.get())}</A>
+                    </div>
+                    <LocalePicker />
+                    <SetupRun settings=settings onready=start_reading />
                 }.into_view(),
             }
         }
     }
 }
 
+/// Colours every other syllable differently, so a child can sound out the word piece by piece.
+fn syllables_view(syllables: Vec<String>) -> impl IntoView {
+    syllables
+        .into_iter()
+        .enumerate()
+        .map(|(i, syllable)| view! {
+            <span class:syllable-odd={i % 2 == 0} class:syllable-even={i % 2 == 1}>{syllable}</span>
+        })
+        .collect_view()
+}
+
+/// The word, syllable by syllable when asked for and its language could split it.
+fn word_view(word: PoolWord, show_syllables: bool) -> View {
+    match show_syllables && !word.syllables.is_empty() {
+        true => syllables_view(word.syllables).into_view(),
+        false => word.text.into_view(),
+    }
+}
+
+fn sync_runs(profile_id: u64, runs: Vec<RunRecord>) {
+    spawn_local(async move {
+        if let Err(e) = progress::store_runs(profile_id, runs).await {
+            progress::log_sync_error("runs", &e);
+        }
+    });
+}
+
+/// Renders a sentence with each of its words opening the dictionary, keeping the punctuation as plain text.
+fn sentence_view(sentence: String, language: &'static Language, provider: DictionaryProvider) -> impl IntoView {
+    sentence
+        .split_inclusive(' ')
+        .map(|token| match split_word(token) {
+            (_, "", _) => token.to_string().into_view(),
+            (leading, word, trailing) => {
+                let word = word.to_string();
+                view! {
+                    {leading.to_string()}
+                    <DictionaryWord word=word.clone() language=language provider=provider.clone()>{word.clone()}</DictionaryWord>
+                    {trailing.to_string()}
+                }.into_view()
+            },
+        })
+        .collect_view()
+}
 
+/// Splits a sentence token into the punctuation before the word, the word, and whatever follows it,
+/// e.g. `"Olá, ` into `"`, `Olá` and `, `. Words keep their hyphens and the apostrophes between letters (`d'água`).
+fn split_word(token: &str) -> (&str, &str, &str) {
+    let start = token.find(char::is_alphabetic).unwrap_or(token.len());
+    let rest = &token[start..];
+    let mut end = 0;
+    let mut chars = rest.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let inner_apostrophe = (c == '\'' || c == '’') && chars.peek().is_some_and(|(_, next)| next.is_alphabetic());
+        match c.is_alphabetic() || c == '-' || inner_apostrophe {
+            true => end = i + c.len_utf8(),
+            false => break,
+        }
+    }
+    (&token[..start], &rest[..end], &rest[end..])
+}
 
 /// 404 - Not Found
 #[component]
@@ -160,7 +655,22 @@ fn NotFound() -> impl IntoView {
         resp.set_status(actix_web::http::StatusCode::NOT_FOUND);
     }
 
+    let locale = i18n::use_locale().locale;
     view! {
-        <h1>"Not Found"</h1>
+        <h1>{move || Msg::NotFound.text(locale.get())}</h1>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_word() {
+        assert_eq!(split_word("pato "), ("", "pato", " "));
+        assert_eq!(split_word("\"Olá, "), ("\"", "Olá", ", "));
+        assert_eq!(split_word("d'água."), ("", "d'água", "."));
+        assert_eq!(split_word("'bem-vindo' "), ("'", "bem-vindo", "' "));
+        assert_eq!(split_word("— "), ("— ", "", ""));
     }
 }
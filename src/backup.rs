@@ -0,0 +1,421 @@
+use leptos::*;
+use leptos_router::*;
+use leptos_use::storage::{use_local_storage, use_local_storage_with_options, JsonCodec, UseStorageOptions};
+use serde::{Serialize, Deserialize};
+use std::fmt;
+use wasm_bindgen_futures::JsFuture;
+
+use crate::app::{Difficulty, GameMode, ReadingMode, DEFAULT_TIME_LIMIT_SECS};
+use crate::curriculum::Lesson;
+use crate::dictionary::{self, DictionaryProvider};
+use crate::i18n::{self, Locale, Msg};
+use crate::languages::{self, DEFAULT_LANGUAGE};
+use crate::outcomes::{self, WordOutcome};
+use crate::profiles;
+use crate::progress::{self, ProfileSettings};
+use crate::review::ReviewCard;
+use crate::scores::RunRecord;
+
+/// Version written in new backups. Bump it when the format changes in a way older versions of the app cannot read.
+pub const BACKUP_VERSION: u32 = 1;
+
+/// Everything kept about a profile, as saved to a backup file.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProfileBackup {
+    pub version: u32,
+    pub profile_name: String,
+    /// Setup screen settings, `letters` being the keys switched on in the keyboard.
+    pub settings: ProfileSettings,
+    pub run_history: Vec<RunRecord>,
+    pub word_outcomes: Vec<WordOutcome>,
+    #[serde(default)]
+    pub review_deck: Vec<ReviewCard>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImportMode {
+    /// Keeps what is already here and adds what the backup has on top.
+    Merge,
+    /// Throws away what is here in favour of the backup.
+    Replace,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BackupError {
+    NotJson,
+    /// Written by a newer version of the app.
+    UnsupportedVersion(u32),
+    /// Valid JSON, but not a backup this version understands.
+    Invalid(String),
+    /// Reads a language this version of the app does not know.
+    UnknownLanguage(String),
+    /// Has a letter switched on that is not on the language's keyboard.
+    UnknownLetter(String),
+    /// Looks words up at an address that is not a web page.
+    UnsafeDictionary(String),
+}
+
+impl BackupError {
+    /// Text shown to the parent in the backup screen, in the app's locale.
+    pub fn message(&self, locale: Locale) -> String {
+        match self {
+            BackupError::NotJson => Msg::NotABackup.text(locale).to_string(),
+            BackupError::UnsupportedVersion(version) => i18n::newer_backup_text(locale, *version),
+            BackupError::Invalid(reason) => i18n::damaged_backup_text(locale, reason),
+            BackupError::UnknownLanguage(code) => i18n::damaged_backup_text(locale, &i18n::unknown_language_text(locale, code)),
+            BackupError::UnknownLetter(letter) => i18n::damaged_backup_text(locale, &i18n::unknown_letter_text(locale, letter)),
+            BackupError::UnsafeDictionary(template) => i18n::damaged_backup_text(locale, &i18n::unsafe_dictionary_text(locale, template)),
+        }
+    }
+}
+
+impl fmt::Display for BackupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message(Locale::default()))
+    }
+}
+
+/// Reads a backup file, checking its version before its contents so newer files get a clear error.
+pub fn parse_backup(json: &str) -> Result<ProfileBackup, BackupError> {
+    #[derive(Deserialize)]
+    struct Header {
+        version: u32,
+    }
+
+    let value: serde_json::Value = serde_json::from_str(json).map_err(|_| BackupError::NotJson)?;
+    let header = Header::deserialize(&value).map_err(|e| BackupError::Invalid(e.to_string()))?;
+    if header.version == 0 || header.version > BACKUP_VERSION {
+        return Err(BackupError::UnsupportedVersion(header.version));
+    }
+
+    let backup: ProfileBackup = serde_json::from_value(value).map_err(|e| BackupError::Invalid(e.to_string()))?;
+    let language = match backup.settings.language.as_str() {
+        "" => DEFAULT_LANGUAGE,
+        code => languages::find_language(code).ok_or_else(|| BackupError::UnknownLanguage(code.to_string()))?,
+    };
+    if let Some(letter) = backup.settings.letters.iter().find(|l| language.letter_variants(l).is_none()) {
+        return Err(BackupError::UnknownLetter(letter.clone()));
+    }
+    match &backup.settings.dictionary {
+        // an empty template is what picking another dictionary saves before one is typed
+        DictionaryProvider::Url(template) if !template.is_empty() && !dictionary::is_web_address(template) => {
+            return Err(BackupError::UnsafeDictionary(template.clone()));
+        },
+        _ => {},
+    }
+    Ok(backup)
+}
+
+/// What the profile looks like after importing `backup` over `current`. The profile keeps its own name either way.
+pub fn import(current: ProfileBackup, backup: ProfileBackup, mode: ImportMode) -> ProfileBackup {
+    match mode {
+        ImportMode::Replace => ProfileBackup { version: BACKUP_VERSION, profile_name: current.profile_name, ..backup },
+        ImportMode::Merge => {
+            let settings = match backup.settings.updated_at > current.settings.updated_at {
+                true => backup.settings,
+                false => current.settings,
+            };
+
+            let mut run_history = current.run_history;
+            progress::merge_runs(&mut run_history, backup.run_history);
+
+            let mut word_outcomes = current.word_outcomes;
+            let mut new_outcomes: Vec<WordOutcome> = backup.word_outcomes.into_iter().filter(|o| !word_outcomes.contains(o)).collect();
+            new_outcomes.sort_by(|a, b| a.at.total_cmp(&b.at));
+            for outcome in new_outcomes {
+                outcomes::record(&mut word_outcomes, outcome);
+            }
+            word_outcomes.sort_by(|a, b| a.at.total_cmp(&b.at));
+
+            // a word in both decks keeps the lower box, so it is not taken for learnt too early
+            let mut review_deck = current.review_deck;
+            for card in backup.review_deck {
+                match review_deck.iter_mut().find(|c| c.is_for(&card.text, &card.reading_mode, &card.language)) {
+                    Some(c) if card.box_number < c.box_number => *c = card,
+                    Some(_) => {},
+                    None => review_deck.push(card),
+                }
+            }
+
+            ProfileBackup {
+                version: BACKUP_VERSION,
+                profile_name: current.profile_name,
+                settings,
+                run_history,
+                word_outcomes,
+                review_deck,
+            }
+        },
+    }
+}
+
+/// The `data:` URL the backup is downloaded from. Encoded like javascript's `encodeURIComponent`, but in Rust,
+/// so the page also renders on the server.
+fn export_url(backup: &ProfileBackup) -> String {
+    let json = serde_json::to_string(backup).unwrap_or_default();
+    let encoded: String = json
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'!' | b'~' | b'*' | b'\'' | b'(' | b')' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect();
+    format!("data:application/json;charset=utf-8,{}", encoded)
+}
+
+/// Saves the current profile to a file, and restores one saved on this or another device.
+#[component]
+pub fn backup_page() -> impl IntoView {
+    let profiles::StoredProfiles { profiles, current_profile, .. } = profiles::use_profiles();
+    let profile_id = current_profile.get_untracked().unwrap_or_default();
+    let profile_name = move || profiles.with(|profiles| profiles.iter().find(|p| p.id == profile_id).map(|p| p.name.clone()).unwrap_or_default());
+
+    let (allowed_chars, set_allowed_chars, _) = use_local_storage::<String, JsonCodec>(profiles::storage_key(profile_id, "allowed_chars"));
+    let (all_words, set_all_words, _) = use_local_storage::<bool, JsonCodec>(profiles::storage_key(profile_id, "all_words"));
+    let (difficulty, set_difficulty, _) = use_local_storage::<Difficulty, JsonCodec>(profiles::storage_key(profile_id, "difficulty"));
+    let (reading_mode, set_reading_mode, _) = use_local_storage::<ReadingMode, JsonCodec>(profiles::storage_key(profile_id, "reading_mode"));
+    let (game_mode, set_game_mode, _) = use_local_storage::<GameMode, JsonCodec>(profiles::storage_key(profile_id, "game_mode"));
+    let (time_limit_secs, set_time_limit_secs, _) = use_local_storage_with_options::<u32, JsonCodec>(
+        profiles::storage_key(profile_id, "time_limit_secs"),
+        UseStorageOptions::default().initial_value(DEFAULT_TIME_LIMIT_SECS),
+    );
+    let (show_syllables, set_show_syllables, _) = use_local_storage::<bool, JsonCodec>(profiles::storage_key(profile_id, "show_syllables"));
+    let (lesson, set_lesson, _) = use_local_storage::<Option<Lesson>, JsonCodec>(profiles::storage_key(profile_id, "lesson"));
+    let (settings_synced_at, set_settings_synced_at, _) = use_local_storage::<f64, JsonCodec>(profiles::storage_key(profile_id, "settings_synced_at"));
+    let (run_history, set_run_history, _) = use_local_storage::<Vec<RunRecord>, JsonCodec>(profiles::storage_key(profile_id, "run_history"));
+    let (word_outcomes, set_word_outcomes, _) = use_local_storage::<Vec<WordOutcome>, JsonCodec>(profiles::storage_key(profile_id, "word_outcomes"));
+    let (review_deck, set_review_deck, _) = use_local_storage::<Vec<ReviewCard>, JsonCodec>(profiles::storage_key(profile_id, "review_deck"));
+    let (language, set_language, _) = use_local_storage::<String, JsonCodec>(profiles::storage_key(profile_id, "language"));
+    let (listen_after_trying, set_listen_after_trying, _) = use_local_storage::<bool, JsonCodec>(profiles::storage_key(profile_id, "listen_after_trying"));
+    let (dictionary, set_dictionary, _) = use_local_storage::<DictionaryProvider, JsonCodec>(profiles::storage_key(profile_id, "dictionary"));
+    let (with_pictures, set_with_pictures, _) = use_local_storage::<bool, JsonCodec>(profiles::storage_key(profile_id, "with_pictures"));
+    let (picture_after_reading, set_picture_after_reading, _) = use_local_storage::<bool, JsonCodec>(profiles::storage_key(profile_id, "picture_after_reading"));
+    // the setup keyboard keeps each key in its own entry
+    let set_keys: Vec<(&str, WriteSignal<bool>)> = languages::keyboard_labels()
+        .into_iter()
+        .map(|label| (label, use_local_storage::<bool, JsonCodec>(profiles::storage_key(profile_id, &format!("tk_{}", label))).1))
+        .collect();
+    let set_keys = store_value(set_keys);
+
+    let current_backup = move || ProfileBackup {
+        version: BACKUP_VERSION,
+        profile_name: profile_name(),
+        settings: ProfileSettings {
+            letters: languages::language(&language.get()).letters_in(&allowed_chars.get()),
+            all_words: all_words.get(),
+            difficulty: difficulty.get(),
+            reading_mode: reading_mode.get(),
+            game_mode: game_mode.get(),
+            time_limit_secs: time_limit_secs.get(),
+            show_syllables: show_syllables.get(),
+            lesson: lesson.get(),
+            language: language.get(),
+            listen_after_trying: listen_after_trying.get(),
+            dictionary: dictionary.get(),
+            with_pictures: with_pictures.get(),
+            picture_after_reading: picture_after_reading.get(),
+            updated_at: settings_synced_at.get(),
+        },
+        run_history: run_history.get(),
+        word_outcomes: word_outcomes.get(),
+        review_deck: review_deck.get(),
+    };
+
+    let export_href = move || export_url(&current_backup());
+    let export_name = move || format!("vamos-ler-{}.json", profile_name().to_lowercase().replace(' ', "-"));
+
+    let locale = i18n::use_locale().locale;
+    let pending = create_rw_signal(None::<ProfileBackup>);
+    let error = create_rw_signal(None::<BackupError>);
+    let imported = create_rw_signal(false);
+
+    let read_file = move |ev: ev::Event| {
+        let input = event_target::<web_sys::HtmlInputElement>(&ev);
+        let Some(file) = input.files().and_then(|files| files.get(0)) else {
+            return;
+        };
+        imported.set(false);
+        spawn_local(async move {
+            let text = JsFuture::from(file.text()).await.ok().and_then(|t| t.as_string()).unwrap_or_default();
+            match parse_backup(&text) {
+                Ok(backup) => {
+                    pending.set(Some(backup));
+                    error.set(None);
+                },
+                Err(e) => {
+                    pending.set(None);
+                    error.set(Some(e));
+                },
+            }
+        });
+    };
+
+    let apply = move |mode: ImportMode| {
+        let Some(backup) = pending.get_untracked() else {
+            return;
+        };
+        let mut result = import(untrack(current_backup), backup, mode);
+        // stamped as a fresh change, so the server copy does not win it back on the next sync
+        result.settings.updated_at = js_sys::Date::now();
+
+        let settings = result.settings.clone();
+        set_allowed_chars.set(languages::language(&settings.language).allowed_chars_for(&settings.letters));
+        set_keys.with_value(|keys| {
+            for (label, set_key) in keys {
+                set_key.set(settings.letters.iter().any(|l| label.eq_ignore_ascii_case(l)));
+            }
+        });
+        set_all_words.set(settings.all_words);
+        set_difficulty.set(settings.difficulty.clone());
+        set_reading_mode.set(settings.reading_mode.clone());
+        set_game_mode.set(settings.game_mode.clone());
+        set_time_limit_secs.set(settings.time_limit_secs);
+        set_show_syllables.set(settings.show_syllables);
+        set_lesson.set(settings.lesson.clone());
+        set_language.set(settings.language.clone());
+        set_listen_after_trying.set(settings.listen_after_trying);
+        set_dictionary.set(settings.dictionary.clone());
+        set_with_pictures.set(settings.with_pictures);
+        set_picture_after_reading.set(settings.picture_after_reading);
+        set_settings_synced_at.set(settings.updated_at);
+        set_run_history.set(result.run_history.clone());
+        set_word_outcomes.set(result.word_outcomes);
+        set_review_deck.set(result.review_deck);
+
+        let runs = result.run_history;
+        spawn_local(async move {
+            if let Err(e) = progress::store_settings(profile_id, settings).await {
+                progress::log_sync_error("settings", &e);
+            }
+            let stored = match mode {
+                ImportMode::Merge => progress::store_runs(profile_id, runs).await,
+                ImportMode::Replace => progress::replace_stored_runs(profile_id, runs).await,
+            };
+            if let Err(e) = stored {
+                progress::log_sync_error("runs", &e);
+            }
+        });
+
+        pending.set(None);
+        imported.set(true);
+    };
+
+    let pending_view = move || pending.get().map(|backup| view! {
+        <div class="backup-pending">
+            {i18n::backup_summary_text(locale.get(), &backup.profile_name, backup.run_history.len(), backup.word_outcomes.len())}
+        </div>
+        <div class="flex-center">
+            <div class="key" on:click=move |_| apply(ImportMode::Merge)>{move || Msg::MergeBackup.text(locale.get())}</div>
+            <div class="key delete-profile" on:click=move |_| apply(ImportMode::Replace)>{move || Msg::ReplaceAll.text(locale.get())}</div>
+        </div>
+    });
+
+    view! {
+        <h1 class="settings-title">{move || Msg::BackupsOf.text(locale.get())}{profile_name}</h1>
+        <div class="flex-center"><A href="/">{move || Msg::Back.text(locale.get())}</A></div>
+        <div class="flex-center">
+            <div class="backup">
+                <a class="key" download=export_name href=export_href>{move || Msg::SaveBackup.text(locale.get())}</a>
+                <label class="key">{move || Msg::RestoreBackup.text(locale.get())}<input type="file" class="backup-file" accept=".json,application/json" on:change=read_file /></label>
+            </div>
+        </div>
+        {pending_view}
+        <div class="backup-done" style:display=move || if imported.get() { "block" } else { "none" }>{move || Msg::BackupRestored.text(locale.get())}</div>
+        <div class="setup-error" style:display=move || if error.get().is_some() { "block" } else { "none" }>{move || error.get().map(|e| e.message(locale.get()))}</div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::outcomes::Outcome;
+    use crate::test_support::{outcome, run_at};
+
+    fn backup(updated_at: f64) -> ProfileBackup {
+        ProfileBackup {
+            version: BACKUP_VERSION,
+            profile_name: "Ana".to_string(),
+            settings: ProfileSettings {
+                letters: vec!["a".to_string(), "p".to_string()],
+                all_words: false,
+                difficulty: Difficulty::Easy,
+                reading_mode: ReadingMode::Words,
+                game_mode: GameMode::Classic,
+                time_limit_secs: 30,
+                show_syllables: false,
+                lesson: None,
+                language: String::new(),
+                listen_after_trying: false,
+                dictionary: DictionaryProvider::Language,
+                with_pictures: false,
+                picture_after_reading: false,
+                updated_at,
+            },
+            run_history: vec![],
+            word_outcomes: vec![],
+            review_deck: vec![],
+        }
+    }
+
+    fn card(text: &str, box_number: usize) -> ReviewCard {
+        ReviewCard { text: text.to_string(), reading_mode: ReadingMode::Words, box_number, due_day: 0, language: String::new() }
+    }
+
+    #[test]
+    fn test_parse_backup() {
+        let json = serde_json::to_string(&backup(1.0)).unwrap();
+        assert_eq!(parse_backup(&json), Ok(backup(1.0)));
+
+        assert_eq!(parse_backup("pato"), Err(BackupError::NotJson));
+        assert_eq!(parse_backup(&json.replace("\"version\":1", "\"version\":2")), Err(BackupError::UnsupportedVersion(2)));
+        assert!(matches!(parse_backup("{\"version\":1}"), Err(BackupError::Invalid(_))));
+        assert_eq!(parse_backup(&json.replace("\"p\"", "\"ç\"")), Err(BackupError::UnknownLetter("ç".to_string())), "letters must be on the keyboard");
+        assert_eq!(parse_backup(&json.replace("\"language\":\"\"", "\"language\":\"xx\"")), Err(BackupError::UnknownLanguage("xx".to_string())));
+        let with_dictionary = |template: &str| {
+            let mut backup = backup(1.0);
+            backup.settings.dictionary = DictionaryProvider::Url(template.to_string());
+            parse_backup(&serde_json::to_string(&backup).unwrap())
+        };
+        assert!(with_dictionary("https://example.com/{}").is_ok());
+        assert!(with_dictionary("").is_ok(), "another dictionary picked but not typed yet");
+        assert_eq!(with_dictionary("javascript:alert('{}')"), Err(BackupError::UnsafeDictionary("javascript:alert('{}')".to_string())));
+        assert!(parse_backup(&json.replace("\"language\":\"\",", "")).is_ok(), "backups from before languages play the default one");
+        let without_time_limit = parse_backup(&json.replace("\"time_limit_secs\":30,", "")).unwrap();
+        assert_eq!(without_time_limit.settings.time_limit_secs, DEFAULT_TIME_LIMIT_SECS);
+    }
+
+    #[test]
+    fn test_export_url() {
+        let mut backup = backup(1.0);
+        backup.profile_name = "Zé & Ana".to_string();
+        let url = export_url(&backup);
+        assert!(url.starts_with("data:application/json;charset=utf-8,%7B%22version%22%3A1%2C"));
+        assert!(url.contains("%22Z%C3%A9%20%26%20Ana%22"), "{}", url);
+    }
+
+    #[test]
+    fn test_import() {
+        let mut current = backup(2.0);
+        current.run_history = vec![run_at(1.0)];
+        current.word_outcomes = vec![outcome("pato", Outcome::Read, 1.0)];
+        current.review_deck = vec![card("chá", 3)];
+        let mut other = backup(1.0);
+        other.profile_name = "Ana (tablet)".to_string();
+        other.settings.all_words = true;
+        other.run_history = vec![run_at(0.5), run_at(1.0)];
+        other.word_outcomes = vec![outcome("pato", Outcome::Read, 0.5), outcome("pato", Outcome::Read, 1.0)];
+        other.review_deck = vec![card("chá", 1), card("lua", 2)];
+
+        let merged = import(current.clone(), other.clone(), ImportMode::Merge);
+        assert_eq!(merged.profile_name, "Ana");
+        assert!(!merged.settings.all_words, "the newest settings win");
+        assert_eq!(merged.run_history, vec![run_at(0.5), run_at(1.0)]);
+        assert_eq!(merged.word_outcomes, vec![outcome("pato", Outcome::Read, 0.5), outcome("pato", Outcome::Read, 1.0)]);
+        assert_eq!(merged.review_deck, vec![card("chá", 1), card("lua", 2)]);
+
+        let replaced = import(current, other.clone(), ImportMode::Replace);
+        assert_eq!(replaced.profile_name, "Ana");
+        assert_eq!(replaced.run_history, other.run_history);
+        assert!(replaced.settings.all_words);
+    }
+}
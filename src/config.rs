@@ -0,0 +1,393 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::app::Difficulty;
+use crate::curriculum::{self, CurriculumError};
+use crate::dictionary::{self, DefinitionsError};
+use crate::languages::{self, Language, DEFAULT_LANGUAGE};
+use crate::lexicanum::{self, LanguageData, Library};
+use crate::pictures::{self, PicturesError};
+use crate::word_source::{HttpJsonSource, TextFileSource, WordSource, WordSourceError};
+
+pub const USAGE: &str = "usage: learn-to-read [--wordlist <path> | --wordlist-url <url>] [--sentences <path>] [--lessons <path>] [--definitions <path>] [--pictures <path>] [--language <code>]... [--store <dir>] [--write-word-index <dir>]
+
+  --wordlist <path>      text file with one word per line     (env LEARN_TO_READ_WORDLIST, default wordlist/wordlist-ao-latest.txt)
+  --wordlist-url <url>   JSON API answering with a word array  (env LEARN_TO_READ_WORDLIST_URL, takes precedence over the file unless --wordlist is given)
+  --sentences <path>     text file with one sentence per line (env LEARN_TO_READ_SENTENCES, default wordlist/sentences-ao-latest.txt)
+  --lessons <path>       text file with the phonics lessons    (env LEARN_TO_READ_LESSONS, default wordlist/lessons-ao.txt)
+  --definitions <path>   text file with `word | definition` lines, optional (env LEARN_TO_READ_DEFINITIONS, default wordlist/definitions-ao.txt)
+  --pictures <path>      text file with `word | path under assets/` lines, optional (env LEARN_TO_READ_PICTURES, default assets/pictures/ao.txt)
+  --language <code>      also play this language, from wordlist/{wordlist,sentences,lessons,definitions}-<code>.txt and assets/pictures/<code>.txt (env LEARN_TO_READ_LANGUAGES, comma separated, e.g. en)
+  --store <dir>          keep profiles and runs in this directory to sync devices (env LEARN_TO_READ_STORE, off by default)
+  --write-word-index <dir>   write the word index of each language (<code>.json) for the static csr build to this directory and exit";
+
+/// Where the server reads its words from. Command line arguments win over `LEARN_TO_READ_*` environment variables.
+/// The wordlist, sentences and lessons are those of the default language; other languages read fixed files.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ServerConfig {
+    pub wordlist_path: String,
+    pub wordlist_url: Option<String>,
+    pub sentences_path: String,
+    pub lessons_path: String,
+    /// Definitions shown in place of an online dictionary. Playing without them is fine, so a missing file is skipped.
+    pub definitions_path: String,
+    /// Manifest of the pictures shown with words. Optional as well.
+    pub pictures_path: String,
+    /// Codes of the languages played besides the default one.
+    pub languages: Vec<String>,
+    /// Directory of the server side progress store. Progress only lives in the browser without it.
+    pub store_dir: Option<String>,
+    /// Write the word indexes to this directory and exit instead of serving, to ship them with the static csr build.
+    pub write_word_index: Option<String>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            wordlist_path: "wordlist/wordlist-ao-latest.txt".to_string(),
+            wordlist_url: None,
+            sentences_path: "wordlist/sentences-ao-latest.txt".to_string(),
+            lessons_path: "wordlist/lessons-ao.txt".to_string(),
+            definitions_path: "wordlist/definitions-ao.txt".to_string(),
+            pictures_path: "assets/pictures/ao.txt".to_string(),
+            languages: vec![],
+            store_dir: None,
+            write_word_index: None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ConfigError {
+    UnknownArgument(String),
+    MissingValue(String),
+    UnknownLanguage(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::UnknownArgument(arg) => write!(f, "unknown argument {}", arg),
+            ConfigError::MissingValue(arg) => write!(f, "missing value for {}", arg),
+            ConfigError::UnknownLanguage(code) => write!(f, "unknown language {}", code),
+        }
+    }
+}
+
+impl ServerConfig {
+    pub fn from_env_and_args() -> Result<Self, ConfigError> {
+        Self::parse(|key| std::env::var(key).ok(), std::env::args().skip(1))
+    }
+
+    fn parse(env: impl Fn(&str) -> Option<String>, args: impl IntoIterator<Item = String>) -> Result<Self, ConfigError> {
+        let mut config = ServerConfig::default();
+
+        if let Some(path) = env("LEARN_TO_READ_WORDLIST") {
+            config.wordlist_path = path;
+        }
+        config.wordlist_url = env("LEARN_TO_READ_WORDLIST_URL");
+        if let Some(path) = env("LEARN_TO_READ_SENTENCES") {
+            config.sentences_path = path;
+        }
+        if let Some(path) = env("LEARN_TO_READ_LESSONS") {
+            config.lessons_path = path;
+        }
+        if let Some(path) = env("LEARN_TO_READ_DEFINITIONS") {
+            config.definitions_path = path;
+        }
+        if let Some(path) = env("LEARN_TO_READ_PICTURES") {
+            config.pictures_path = path;
+        }
+        config.store_dir = env("LEARN_TO_READ_STORE");
+        let mut env_languages: Vec<String> = env("LEARN_TO_READ_LANGUAGES")
+            .map(|codes| codes.split(',').map(|c| c.trim().to_string()).filter(|c| !c.is_empty()).collect())
+            .unwrap_or_default();
+        let mut arg_languages: Vec<String> = vec![];
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or_else(|| ConfigError::MissingValue(arg.clone()));
+            match arg.as_str() {
+                "--wordlist" => {
+                    // a file given on the command line wins over a url from the environment
+                    config.wordlist_path = value()?;
+                    config.wordlist_url = None;
+                },
+                "--wordlist-url" => config.wordlist_url = Some(value()?),
+                "--sentences" => config.sentences_path = value()?,
+                "--lessons" => config.lessons_path = value()?,
+                "--definitions" => config.definitions_path = value()?,
+                "--pictures" => config.pictures_path = value()?,
+                "--language" => arg_languages.push(value()?),
+                "--store" => config.store_dir = Some(value()?),
+                "--write-word-index" => config.write_word_index = Some(value()?),
+                _ => return Err(ConfigError::UnknownArgument(arg.clone())),
+            }
+        }
+
+        if !arg_languages.is_empty() {
+            env_languages = arg_languages;
+        }
+        for code in env_languages {
+            if languages::find_language(&code).is_none() {
+                return Err(ConfigError::UnknownLanguage(code));
+            }
+            if code != DEFAULT_LANGUAGE.code && !config.languages.contains(&code) {
+                config.languages.push(code);
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// Where an extra language reads its wordlist, sentences, lessons, definitions and picture manifest from.
+    pub fn language_paths(code: &str) -> [String; 5] {
+        let [wordlist, sentences, lessons, definitions] = ["wordlist", "sentences", "lessons", "definitions"].map(|file| format!("wordlist/{}-{}.txt", file, code));
+        [wordlist, sentences, lessons, definitions, format!("assets/pictures/{}.txt", code)]
+    }
+}
+
+/// What got loaded at startup, printed before the server starts listening.
+#[derive(Clone, Debug)]
+pub struct WordlistReport {
+    pub language: String,
+    pub wordlist_source: String,
+    pub lines: usize,
+    pub per_difficulty: Vec<(Difficulty, usize)>,
+    pub sentences_source: String,
+    pub sentences: usize,
+    pub lessons_source: String,
+    pub lessons: usize,
+    /// `None` when there is no definitions file.
+    pub definitions_source: Option<String>,
+    pub definitions: usize,
+    /// `None` when there is no picture manifest.
+    pub pictures_source: Option<String>,
+    pub pictures: usize,
+}
+
+impl WordlistReport {
+    /// Reasons the loaded data cannot be used to play, if any.
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = vec![];
+        if self.lines == 0 {
+            problems.push(format!("{} has no words", self.wordlist_source));
+        }
+        for (diff, _) in self.per_difficulty.iter().filter(|(_, count)| *count == 0) {
+            problems.push(format!("no words fit difficulty {:?}", diff));
+        }
+        if self.sentences == 0 {
+            problems.push(format!("{} has no sentences", self.sentences_source));
+        }
+        if self.lessons == 0 {
+            problems.push(format!("{} has no lessons", self.lessons_source));
+        }
+        problems
+    }
+}
+
+impl fmt::Display for WordlistReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "language: {}", self.language)?;
+        writeln!(f, "wordlist: {} ({} lines)", self.wordlist_source, self.lines)?;
+        for (diff, count) in self.per_difficulty.iter() {
+            writeln!(f, "  {:?}: {} words", diff, count)?;
+        }
+        writeln!(f, "sentences: {} ({} lines)", self.sentences_source, self.sentences)?;
+        write!(f, "lessons: {} ({} lessons)", self.lessons_source, self.lessons)?;
+        match &self.definitions_source {
+            Some(source) => write!(f, "\ndefinitions: {} ({} words)", source, self.definitions),
+            None => write!(f, "\ndefinitions: none"),
+        }?;
+        match &self.pictures_source {
+            Some(source) => write!(f, "\npictures: {} ({} words)", source, self.pictures),
+            None => write!(f, "\npictures: none"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum StartupError {
+    Source(WordSourceError),
+    Curriculum(CurriculumError),
+    Definitions(DefinitionsError),
+    Pictures(PicturesError),
+    Invalid { report: WordlistReport, problems: Vec<String> },
+}
+
+impl fmt::Display for StartupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StartupError::Source(error) => write!(f, "{}", error),
+            StartupError::Curriculum(error) => write!(f, "couldnt read the lessons: {}", error),
+            StartupError::Definitions(error) => write!(f, "couldnt read the definitions: {}", error),
+            StartupError::Pictures(error) => write!(f, "couldnt read the pictures: {}", error),
+            StartupError::Invalid { report, problems } => write!(f, "{}\n\ncannot start: {}", report, problems.join(", ")),
+        }
+    }
+}
+
+impl From<WordSourceError> for StartupError {
+    fn from(error: WordSourceError) -> Self {
+        StartupError::Source(error)
+    }
+}
+
+impl From<CurriculumError> for StartupError {
+    fn from(error: CurriculumError) -> Self {
+        StartupError::Curriculum(error)
+    }
+}
+
+impl From<DefinitionsError> for StartupError {
+    fn from(error: DefinitionsError) -> Self {
+        StartupError::Definitions(error)
+    }
+}
+
+impl From<PicturesError> for StartupError {
+    fn from(error: PicturesError) -> Self {
+        StartupError::Pictures(error)
+    }
+}
+
+/// Everything the server functions need, loaded once before the workers start.
+pub struct LoadedData {
+    pub library: Library,
+    /// One report per language, the default one first.
+    pub reports: Vec<WordlistReport>,
+}
+
+pub async fn load_data(config: &ServerConfig) -> Result<LoadedData, StartupError> {
+    let sentences = TextFileSource::new(config.sentences_path.clone());
+    let lessons = TextFileSource::new(config.lessons_path.clone());
+    let (definitions, pictures) = (&config.definitions_path, &config.pictures_path);
+    let default = match &config.wordlist_url {
+        Some(url) => load_language(DEFAULT_LANGUAGE, &HttpJsonSource::new(url.clone()), &sentences, &lessons, definitions, pictures).await?,
+        None => load_language(DEFAULT_LANGUAGE, &TextFileSource::new(config.wordlist_path.clone()), &sentences, &lessons, definitions, pictures).await?,
+    };
+
+    let mut library = HashMap::new();
+    let mut reports = vec![default.1];
+    library.insert(DEFAULT_LANGUAGE.code.to_string(), default.0);
+    for code in config.languages.iter() {
+        let [wordlist, sentences, lessons, definitions, pictures] = ServerConfig::language_paths(code);
+        let language = languages::language(code);
+        let (data, report) = load_language(language, &TextFileSource::new(wordlist), &TextFileSource::new(sentences), &TextFileSource::new(lessons), &definitions, &pictures).await?;
+        library.insert(code.clone(), data);
+        reports.push(report);
+    }
+
+    Ok(LoadedData { library: Library(library), reports })
+}
+
+async fn load_language(language: &'static Language, wordlist: &impl WordSource, sentences_source: &impl WordSource, lessons_source: &impl WordSource, definitions_path: &str, pictures_path: &str) -> Result<(LanguageData, WordlistReport), StartupError> {
+    let words = wordlist.fetch_words().await?;
+    let lines = words.len();
+    let words = lexicanum::classify_words(language, words);
+    let sentences = sentences_source.fetch_words().await?;
+    let lessons = curriculum::parse_lessons(&lessons_source.fetch_words().await?, language)?;
+    let definitions_source = std::path::Path::new(definitions_path).exists().then(|| TextFileSource::new(definitions_path));
+    let definitions = match &definitions_source {
+        Some(source) => dictionary::parse_definitions(&source.fetch_lines().await?)?,
+        None => Default::default(),
+    };
+    let pictures_source = std::path::Path::new(pictures_path).exists().then(|| TextFileSource::new(pictures_path));
+    let pictures = match &pictures_source {
+        Some(source) => pictures::parse_pictures(&source.fetch_lines().await?)?,
+        None => Default::default(),
+    };
+
+    let report = WordlistReport {
+        language: language.code.to_string(),
+        wordlist_source: wordlist.describe(),
+        lines,
+        per_difficulty: Difficulty::ALL.iter().map(|d| (d.clone(), words.get(d).map_or(0, |w| w.len()))).collect(),
+        sentences_source: sentences_source.describe(),
+        sentences: sentences.len(),
+        lessons_source: lessons_source.describe(),
+        lessons: lessons.len(),
+        definitions_source: definitions_source.map(|s| s.describe()),
+        definitions: definitions.len(),
+        pictures_source: pictures_source.map(|s| s.describe()),
+        pictures: pictures.len(),
+    };
+
+    match report.problems() {
+        problems if problems.is_empty() => Ok((LanguageData { language, words, sentences, lessons, definitions, pictures }, report)),
+        problems => Err(StartupError::Invalid { report, problems }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_config() {
+        assert_eq!(ServerConfig::parse(|_| None, args(&[])), Ok(ServerConfig::default()));
+
+        let env = |key: &str| match key {
+            "LEARN_TO_READ_WORDLIST" => Some("/data/words.txt".to_string()),
+            "LEARN_TO_READ_SENTENCES" => Some("/data/sentences.txt".to_string()),
+            "LEARN_TO_READ_LESSONS" => Some("/data/lessons.txt".to_string()),
+            _ => None,
+        };
+        let config = ServerConfig::parse(env, args(&["--sentences", "frases.txt", "--wordlist-url", "http://localhost/words", "--store", "progress"])).unwrap();
+        assert_eq!(config.wordlist_path, "/data/words.txt");
+        assert_eq!(config.sentences_path, "frases.txt", "command line wins over the environment");
+        assert_eq!(config.wordlist_url, Some("http://localhost/words".to_string()));
+        assert_eq!(config.lessons_path, "/data/lessons.txt");
+        assert_eq!(config.definitions_path, "wordlist/definitions-ao.txt");
+        assert_eq!(config.pictures_path, "assets/pictures/ao.txt");
+        assert_eq!(config.store_dir, Some("progress".to_string()));
+        assert_eq!(config.write_word_index, None);
+        let config = ServerConfig::parse(|_| None, args(&["--write-word-index", "wordlist/word-index"])).unwrap();
+        assert_eq!(config.write_word_index, Some("wordlist/word-index".to_string()));
+        assert_eq!(config.languages, Vec::<String>::new());
+
+        let env = |key: &str| (key == "LEARN_TO_READ_LANGUAGES").then(|| "en, pt".to_string());
+        assert_eq!(ServerConfig::parse(env, args(&[])).map(|c| c.languages), Ok(vec!["en".to_string()]), "the default language is always played");
+        assert_eq!(ServerConfig::parse(env, args(&["--language", "pt"])).map(|c| c.languages), Ok(vec![]), "command line wins over the environment");
+        assert_eq!(ServerConfig::parse(|_| None, args(&["--language", "xx"])), Err(ConfigError::UnknownLanguage("xx".to_string())));
+
+        let env = |key: &str| (key == "LEARN_TO_READ_WORDLIST_URL").then(|| "http://localhost/words".to_string());
+        let config = ServerConfig::parse(env, args(&["--wordlist", "palavras.txt"])).unwrap();
+        assert_eq!((config.wordlist_path.as_str(), config.wordlist_url), ("palavras.txt", None), "a wordlist on the command line wins over a url in the environment");
+        let config = ServerConfig::parse(|_| None, args(&["--wordlist-url", "http://localhost/words", "--wordlist", "palavras.txt"])).unwrap();
+        assert_eq!(config.wordlist_url, None, "the last of the two wins");
+        let config = ServerConfig::parse(|_| None, args(&["--wordlist", "palavras.txt", "--wordlist-url", "http://localhost/words"])).unwrap();
+        assert_eq!(config.wordlist_url, Some("http://localhost/words".to_string()));
+
+        assert_eq!(ServerConfig::parse(|_| None, args(&["--wordlist"])), Err(ConfigError::MissingValue("--wordlist".to_string())));
+        assert_eq!(ServerConfig::parse(|_| None, args(&["--words", "x"])), Err(ConfigError::UnknownArgument("--words".to_string())));
+    }
+
+    #[test]
+    fn test_report_problems() {
+        let mut report = WordlistReport {
+            language: "pt".to_string(),
+            wordlist_source: "file words.txt".to_string(),
+            lines: 3,
+            per_difficulty: Difficulty::ALL.iter().map(|d| (d.clone(), 1)).collect(),
+            sentences_source: "file sentences.txt".to_string(),
+            sentences: 2,
+            lessons_source: "file lessons.txt".to_string(),
+            lessons: 1,
+            definitions_source: None,
+            definitions: 0,
+            pictures_source: None,
+            pictures: 0,
+        };
+        assert!(report.problems().is_empty());
+
+        report.per_difficulty[4].1 = 0;
+        report.sentences = 0;
+        report.lessons = 0;
+        assert_eq!(report.problems(), vec!["no words fit difficulty Hardest", "file sentences.txt has no sentences", "file lessons.txt has no lessons"]);
+    }
+}
@@ -0,0 +1,144 @@
+use leptos::{server, ServerFnError};
+use serde::{Serialize, Deserialize};
+use std::fmt;
+use cfg_if::cfg_if;
+
+use crate::languages::Language;
+use crate::word_index;
+
+/// One step of the phonics curriculum. Lessons are taught in order, each one adding letters to the ones before it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lesson {
+    /// Position in the curriculum, starting at 1.
+    pub number: usize,
+    pub name: String,
+    /// Letters introduced by this lesson.
+    pub new_letters: Vec<String>,
+    /// Every letter taught so far, this lesson's included.
+    pub letters: Vec<String>,
+    /// Letter pairs read as a single sound that this lesson practises, e.g. "lh".
+    pub digraphs: Vec<String>,
+}
+
+impl Lesson {
+    /// The letter filter that plays this lesson on the keyboard of its language.
+    pub fn allowed_chars(&self, language: &Language) -> String {
+        language.allowed_chars_for(&self.letters)
+    }
+
+    /// What the word pool should prefer while playing this lesson: the new letters and the target digraphs.
+    pub fn focus(&self) -> Vec<String> {
+        self.new_letters.iter().chain(self.digraphs.iter()).cloned().collect()
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CurriculumError {
+    /// The lesson is not written as `name | new letters | digraphs`.
+    Malformed { lesson: usize },
+    /// A letter that is not on the setup keyboard.
+    UnknownLetter { lesson: usize, letter: String },
+    /// A digraph using letters that have not been taught yet.
+    DigraphNotTaught { lesson: usize, digraph: String },
+}
+
+impl fmt::Display for CurriculumError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CurriculumError::Malformed { lesson } => write!(f, "lesson {} is not `name | new letters | digraphs`", lesson),
+            CurriculumError::UnknownLetter { lesson, letter } => write!(f, "lesson {} uses {}, which is not on the keyboard", lesson, letter),
+            CurriculumError::DigraphNotTaught { lesson, digraph } => write!(f, "lesson {} practises {} before all of its letters are taught", lesson, digraph),
+        }
+    }
+}
+
+impl std::error::Error for CurriculumError {}
+
+/// Parses the lessons file, one lesson per line in teaching order: `name | new letters | digraphs`,
+/// with letters and digraphs separated by spaces, e.g. `Lh | h | lh nh ch`. Letters must be on the language's keyboard.
+pub fn parse_lessons(lines: &[String], language: &Language) -> Result<Vec<Lesson>, CurriculumError> {
+    let mut lessons: Vec<Lesson> = vec![];
+    let mut letters: Vec<String> = vec![];
+
+    for (i, l) in lines.iter().enumerate() {
+        let lesson = i + 1;
+        let fields: Vec<&str> = l.split('|').map(|f| f.trim()).collect();
+        let [name, new_letters, digraphs] = fields.as_slice() else {
+            return Err(CurriculumError::Malformed { lesson });
+        };
+        if name.is_empty() {
+            return Err(CurriculumError::Malformed { lesson });
+        }
+
+        let new_letters: Vec<String> = new_letters.split_whitespace().map(|l| l.to_lowercase()).collect();
+        if let Some(letter) = new_letters.iter().find(|l| language.letter_variants(l).is_none()) {
+            return Err(CurriculumError::UnknownLetter { lesson, letter: letter.clone() });
+        }
+        for letter in new_letters.iter() {
+            if !letters.contains(letter) {
+                letters.push(letter.clone());
+            }
+        }
+
+        let digraphs: Vec<String> = digraphs.split_whitespace().map(|d| d.to_lowercase()).collect();
+        if let Some(digraph) = digraphs.iter().find(|d| d.chars().any(|c| !letters.contains(&c.to_string()))) {
+            return Err(CurriculumError::DigraphNotTaught { lesson, digraph: digraph.clone() });
+        }
+
+        lessons.push(Lesson {
+            number: lesson,
+            name: name.to_string(),
+            new_letters,
+            letters: letters.clone(),
+            digraphs,
+        });
+    }
+
+    Ok(lessons)
+}
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use crate::lexicanum::library;
+    }
+}
+
+/// Lessons of a language available in the setup screen, in teaching order.
+#[server]
+pub async fn get_lessons(language: String) -> Result<Vec<Lesson>, ServerFnError> {
+    let library = library().await?;
+    Ok(library.get(&language)?.lessons.clone())
+}
+
+/// The lessons from the server, or from the word index when there is no server to ask.
+pub async fn fetch_lessons(language: String) -> Result<Vec<Lesson>, ServerFnError> {
+    word_index::with_local_fallback(get_lessons(language.clone()), word_index::local_lessons(language)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::lines;
+    use crate::languages::{ENGLISH, PORTUGUESE};
+
+    #[test]
+    fn test_parse_lessons() {
+        let lessons = parse_lessons(&lines(&["Vogais | a e i o u |", "P | p |", "Lh | L H | lh"]), &PORTUGUESE).unwrap();
+
+        assert_eq!(lessons.len(), 3);
+        assert_eq!(lessons[1].number, 2);
+        assert_eq!(lessons[1].letters, vec!["a", "e", "i", "o", "u", "p"]);
+        assert_eq!(lessons[2].new_letters, vec!["l", "h"]);
+        assert_eq!(lessons[2].focus(), vec!["l", "h", "lh"]);
+        assert_eq!(lessons[1].allowed_chars(&PORTUGUESE), "aAàÀáÁâÂãÃeEèÈéÉêÊiIíÍoOôÔòÒóÓõÕuUùÙúÚpP");
+        assert_eq!(lessons[1].allowed_chars(&ENGLISH), "aAeEiIoOuUpP");
+    }
+
+    #[test]
+    fn test_parse_lessons_errors() {
+        assert_eq!(parse_lessons(&lines(&["Vogais | a e i o u"]), &PORTUGUESE), Err(CurriculumError::Malformed { lesson: 1 }));
+        assert_eq!(parse_lessons(&lines(&[" | a |"]), &PORTUGUESE), Err(CurriculumError::Malformed { lesson: 1 }));
+        assert_eq!(parse_lessons(&lines(&["Vogais | a e i o u |", "Ç | ç |"]), &PORTUGUESE), Err(CurriculumError::UnknownLetter { lesson: 2, letter: "ç".to_string() }));
+        assert_eq!(parse_lessons(&lines(&["Vogais | a e i o u |", "N | n | nh"]), &PORTUGUESE), Err(CurriculumError::DigraphNotTaught { lesson: 2, digraph: "nh".to_string() }));
+    }
+}
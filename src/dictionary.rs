@@ -0,0 +1,159 @@
+use cfg_if::cfg_if;
+use leptos::*;
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::i18n::{self, Msg};
+use crate::languages::Language;
+use crate::word_index;
+
+/// Where tapping a word looks it up. Each profile picks its own.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum DictionaryProvider {
+    /// The online dictionary of the language being read, e.g. Priberam for Portuguese.
+    #[default]
+    Language,
+    /// Any online dictionary, with `{}` standing for the word, e.g. `https://www.infopedia.pt/dicionarios/lingua-portuguesa/{}`.
+    Url(String),
+    /// The definitions file the server was started with, shown next to the word. Works offline.
+    Local,
+}
+
+/// Example shown in the box where a dictionary URL is typed.
+pub const URL_TEMPLATE_EXAMPLE: &str = "https://…/{}";
+
+impl DictionaryProvider {
+    /// The page to open for a word, or `None` when the definition is shown in place.
+    pub fn link(&self, language: &Language, word: &str) -> Option<String> {
+        match self {
+            DictionaryProvider::Language => Some(language.dictionary_link(word)),
+            DictionaryProvider::Url(template) if is_web_address(template) && template.contains("{}") => Some(template.replace("{}", &word.to_lowercase())),
+            // a template without a place for the word would open the same page for every word,
+            // and one that is not a web page (`javascript:`, `data:`, …) must never end up in a link
+            DictionaryProvider::Url(_) => Some(language.dictionary_link(word)),
+            DictionaryProvider::Local => None,
+        }
+    }
+}
+
+/// Whether a dictionary URL points at a web page, the only kind of address a word may link to.
+pub fn is_web_address(template: &str) -> bool {
+    let template = template.to_ascii_lowercase();
+    template.starts_with("http://") || template.starts_with("https://")
+}
+
+#[derive(Debug, PartialEq)]
+pub enum DefinitionsError {
+    /// The line is not written as `word | definition`.
+    Malformed { line: usize },
+}
+
+impl fmt::Display for DefinitionsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DefinitionsError::Malformed { line } => write!(f, "definition {} is not `word | definition`", line),
+        }
+    }
+}
+
+impl std::error::Error for DefinitionsError {}
+
+/// Parses the definitions file, one word per line: `word | definition`, e.g. `pato | Ave que nada, de bico largo.`
+/// Blank lines and lines starting with `#` are skipped, but still counted in error line numbers. Words are looked up in lowercase.
+pub fn parse_definitions(lines: &[String]) -> Result<BTreeMap<String, String>, DefinitionsError> {
+    let mut definitions = BTreeMap::new();
+    for (i, l) in lines.iter().enumerate().filter(|(_, l)| !l.trim().is_empty() && !l.trim().starts_with('#')) {
+        match l.split_once('|').map(|(w, d)| (w.trim(), d.trim())) {
+            Some((word, definition)) if !word.is_empty() && !definition.is_empty() => {
+                definitions.insert(word.to_lowercase(), definition.to_string());
+            },
+            _ => return Err(DefinitionsError::Malformed { line: i + 1 }),
+        }
+    }
+    Ok(definitions)
+}
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use crate::lexicanum::library;
+    }
+}
+
+/// The definition of a word in the server's definitions file for the language, if it has one.
+#[server]
+pub async fn get_definition(language: String, word: String) -> Result<Option<String>, ServerFnError> {
+    let library = library().await?;
+    Ok(library.get(&language)?.definitions.get(&word.to_lowercase()).cloned())
+}
+
+/// The definition from the server, or from the word index when there is no server to ask.
+pub async fn fetch_definition(language: String, word: String) -> Result<Option<String>, ServerFnError> {
+    word_index::with_local_fallback(get_definition(language.clone(), word.clone()), word_index::local_definition(language, word)).await
+}
+
+/// A word that opens its dictionary entry when tapped: a link to the online dictionary,
+/// or a popover with the local definition.
+#[component]
+pub fn dictionary_word(word: String, language: &'static Language, provider: DictionaryProvider, children: Children) -> impl IntoView {
+    if let Some(href) = provider.link(language, &word) {
+        return view! { <a target="window" href=href>{children()}</a> }.into_view();
+    }
+
+    let locale = i18n::use_locale().locale;
+    let open = create_rw_signal(false);
+    let definition = create_local_resource(
+        move || open.get(),
+        move |open| {
+            let word = word.clone();
+            async move {
+                match open {
+                    true => fetch_definition(language.code.to_string(), word).await.ok().flatten(),
+                    false => None,
+                }
+            }
+        },
+    );
+    let popover = move || open.get().then(|| view! {
+        <span class="definition-popover" on:click=move |ev| { ev.stop_propagation(); open.set(false) }>
+            {move || match definition.get() {
+                Some(Some(definition)) => definition,
+                Some(None) => Msg::NoDefinition.text(locale.get()).to_string(),
+                None => "…".to_string(),
+            }}
+        </span>
+    });
+
+    view! {
+        <span class="dictionary-word" on:click=move |_| open.update(|o| *o = !*o)>{children()}{popover}</span>
+    }.into_view()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::lines;
+    use crate::languages::PORTUGUESE;
+
+    #[test]
+    fn test_link() {
+        assert_eq!(DictionaryProvider::Language.link(&PORTUGUESE, "Pato"), Some("https://dicionario.priberam.org/pato".to_string()));
+        assert_eq!(DictionaryProvider::Url("https://example.com/?q={}".to_string()).link(&PORTUGUESE, "Pato"), Some("https://example.com/?q=pato".to_string()));
+        assert_eq!(DictionaryProvider::Url("https://example.com/".to_string()).link(&PORTUGUESE, "pato"), Some("https://dicionario.priberam.org/pato".to_string()));
+        assert_eq!(DictionaryProvider::Url("HTTP://example.com/{}".to_string()).link(&PORTUGUESE, "pato"), Some("HTTP://example.com/pato".to_string()));
+        assert_eq!(DictionaryProvider::Url("javascript:alert('{}')".to_string()).link(&PORTUGUESE, "pato"), Some("https://dicionario.priberam.org/pato".to_string()));
+        assert_eq!(DictionaryProvider::Url("//example.com/{}".to_string()).link(&PORTUGUESE, "pato"), Some("https://dicionario.priberam.org/pato".to_string()));
+        assert_eq!(DictionaryProvider::Local.link(&PORTUGUESE, "pato"), None);
+    }
+
+    #[test]
+    fn test_parse_definitions() {
+        let definitions = parse_definitions(&lines(&["Pato | Ave que nada.", "bola|Objeto redondo | para jogar."])).unwrap();
+        assert_eq!(definitions.get("pato").map(|d| d.as_str()), Some("Ave que nada."));
+        assert_eq!(definitions.get("bola").map(|d| d.as_str()), Some("Objeto redondo | para jogar."), "only the first bar splits");
+
+        assert_eq!(parse_definitions(&lines(&["pato | Ave.", "bola"])), Err(DefinitionsError::Malformed { line: 2 }));
+        assert_eq!(parse_definitions(&lines(&["pato |"])), Err(DefinitionsError::Malformed { line: 1 }));
+        assert_eq!(parse_definitions(&lines(&["# palavra | definição", "", "pato | Ave.", "bola"])), Err(DefinitionsError::Malformed { line: 4 }), "lines are numbered as in the file");
+    }
+}
@@ -0,0 +1,423 @@
+use leptos::*;
+use leptos_use::storage::{use_local_storage, JsonCodec};
+use serde::{Serialize, Deserialize};
+
+use crate::app::{GameMode, ReadingMode};
+
+/// Language of the app's own text. Independent from the language of the words being read,
+/// so a Portuguese speaking parent can set up English words and the other way around.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum Locale {
+    #[default]
+    Pt,
+    En,
+}
+
+impl Locale {
+    pub const ALL: [Locale; 2] = [Locale::Pt, Locale::En];
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Locale::Pt => "pt",
+            Locale::En => "en",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Locale> {
+        Locale::ALL.into_iter().find(|l| l.code() == code)
+    }
+
+    /// Name shown in the locale selector, in the locale itself.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Locale::Pt => "Português",
+            Locale::En => "English",
+        }
+    }
+
+    /// The locale the reader prefers most, from an `Accept-Language` header (or the browser's language list
+    /// written the same way), e.g. `en-GB,en;q=0.9,pt;q=0.8`. `None` if none of them is translated.
+    pub fn from_accept_language(header: &str) -> Option<Locale> {
+        let mut preferred: Vec<(f32, Locale)> = header
+            .split(',')
+            .filter_map(|entry| {
+                let mut parts = entry.split(';');
+                let tag = parts.next()?.trim().to_lowercase();
+                let quality = parts
+                    .find_map(|p| p.trim().strip_prefix("q="))
+                    .map_or(Some(1.0), |q| q.trim().parse::<f32>().ok())?;
+                let primary = tag.split('-').next()?;
+                Locale::from_code(primary).map(|l| (quality, l))
+            })
+            .filter(|(quality, _)| *quality > 0.0)
+            .collect();
+        // stable, so equally preferred locales keep the header's order
+        preferred.sort_by(|a, b| b.0.total_cmp(&a.0));
+        preferred.first().map(|(_, locale)| *locale)
+    }
+}
+
+/// Every piece of fixed text in the app's screens.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Msg {
+    AppTitle,
+    AllowedLetters,
+    Lessons,
+    AllLetters,
+    Syllables,
+    Words,
+    Sentences,
+    Classic,
+    TimeAttack,
+    Difficulty,
+    Start,
+    ReadAlone,
+    NeededHelp,
+    Left,
+    WordsLeft,
+    SentencesLeft,
+    SecondsLeftReadSoFar,
+    WordsRead,
+    Reading,
+    SwitchProfile,
+    Progress,
+    Backups,
+    CouldNotFetchWords,
+    NotFound,
+    Listen,
+    TriedIt,
+    ListenAfterTrying,
+    Dictionary,
+    DictionaryOnline,
+    DictionaryOther,
+    DictionaryLocal,
+    NoDefinition,
+    WithPictures,
+    PictureAfterReading,
+    ShowPicture,
+    Spelling,
+    Recognition,
+    Next,
+    Finished,
+    WordsPerMinute,
+    NewRecord,
+    ToPractise,
+    DaysInARow,
+    BestStreak,
+    BackButton,
+    Back,
+    WhoIsReading,
+    Name,
+    NewReader,
+    Save,
+    Cancel,
+    Rename,
+    Delete,
+    ReallyDelete,
+    ProgressOf,
+    Letters,
+    NoWordsReadYet,
+    NextLetters,
+    ReadsAllLetters,
+    KeepPractising,
+    ReadyToAdd,
+    DaysReading,
+    BackupsOf,
+    SaveBackup,
+    RestoreBackup,
+    MergeBackup,
+    ReplaceAll,
+    BackupRestored,
+    UnknownDifficulty,
+    EmptyFilter,
+    NoMatchingWords,
+    WordlistNotLoaded,
+    NoWordsInLanguage,
+    EmptyName,
+    DuplicateName,
+    UnknownProfile,
+    NotABackup,
+}
+
+impl Msg {
+    /// The text in each locale, in the order of [`Locale::ALL`].
+    fn translations(self) -> [&'static str; 2] {
+        match self {
+            Msg::AppTitle => ["Vamos Ler!", "Let's Read!"],
+            Msg::AllowedLetters => ["letras permitidas:", "letters allowed:"],
+            Msg::Lessons => ["lições:", "lessons:"],
+            Msg::AllLetters => ["Todas", "All"],
+            Msg::Syllables => ["Sílabas", "Syllables"],
+            Msg::Words => ["Palavras", "Words"],
+            Msg::Sentences => ["Frases", "Sentences"],
+            Msg::Classic => ["Normal", "Normal"],
+            Msg::TimeAttack => ["Contra-relógio", "Against the clock"],
+            Msg::Difficulty => ["Dificuldade", "Difficulty"],
+            Msg::Start => ["COMEÇAR", "START"],
+            Msg::ReadAlone => ["Li bem!", "I read it!"],
+            Msg::NeededHelp => ["Precisei de ajuda", "I needed help"],
+            Msg::Left => ["Faltam ", "Still "],
+            Msg::WordsLeft => [" palavras!", " words to go!"],
+            Msg::SentencesLeft => [" frases!", " sentences to go!"],
+            Msg::SecondsLeftReadSoFar => [" segundos! Já leste ", " seconds! You have read "],
+            Msg::WordsRead => [" palavras.", " words."],
+            Msg::Reading => ["A ler: ", "Reading: "],
+            Msg::SwitchProfile => ["(trocar)", "(switch)"],
+            Msg::Progress => ["(progresso)", "(progress)"],
+            Msg::Backups => ["(cópias)", "(backups)"],
+            Msg::CouldNotFetchWords => ["Não foi possível ir buscar palavras. Tenta outra vez!", "Could not fetch the words. Try again!"],
+            Msg::NotFound => ["Página não encontrada", "Page not found"],
+            Msg::Listen => ["Ouvir", "Listen"],
+            Msg::TriedIt => ["Já tentei, quero ouvir", "I tried, let me listen"],
+            Msg::ListenAfterTrying => ["Ouvir só depois de tentar", "Listen only after trying"],
+            Msg::Dictionary => ["dicionário:", "dictionary:"],
+            Msg::DictionaryOnline => ["Da língua", "The language's"],
+            Msg::DictionaryOther => ["Outro", "Another"],
+            Msg::DictionaryLocal => ["Definições", "Definitions"],
+            Msg::NoDefinition => ["Ainda não há definição desta palavra.", "This word has no definition yet."],
+            Msg::WithPictures => ["Com imagens", "With pictures"],
+            Msg::PictureAfterReading => ["Imagem só depois de ler", "Picture only after reading"],
+            Msg::ShowPicture => ["Já li, quero ver a imagem", "I read it, show me the picture"],
+            Msg::Spelling => ["Escrever", "Spelling"],
+            Msg::Recognition => ["Escolher", "Choosing"],
+            Msg::Next => ["Seguinte", "Next"],
+            Msg::Finished => ["Acabou!", "All done!"],
+            Msg::WordsPerMinute => [" palavras por minuto!", " words per minute!"],
+            Msg::NewRecord => ["Novo recorde!", "New record!"],
+            Msg::ToPractise => ["Para treinar: ", "To practise: "],
+            Msg::DaysInARow => ["Dias seguidos a ler: ", "Days reading in a row: "],
+            Msg::BestStreak => [" (melhor: ", " (best: "],
+            Msg::BackButton => ["VOLTAR", "BACK"],
+            Msg::Back => ["Voltar", "Back"],
+            Msg::WhoIsReading => ["Quem vai ler?", "Who is reading?"],
+            Msg::Name => ["Nome", "Name"],
+            Msg::NewReader => ["Novo leitor", "New reader"],
+            Msg::Save => ["Guardar", "Save"],
+            Msg::Cancel => ["Cancelar", "Cancel"],
+            Msg::Rename => ["Mudar nome", "Rename"],
+            Msg::Delete => ["Apagar", "Delete"],
+            Msg::ReallyDelete => ["Apagar mesmo?", "Really delete?"],
+            Msg::ProgressOf => ["Progresso de ", "Progress of "],
+            Msg::Letters => ["Letras", "Letters"],
+            Msg::NoWordsReadYet => ["Ainda não há palavras lidas.", "No words read yet."],
+            Msg::NextLetters => ["Próximas letras", "Next letters"],
+            Msg::ReadsAllLetters => ["Já lê com todas as letras.", "Already reads with every letter."],
+            Msg::KeepPractising => ["Continuar a treinar as letras de agora.", "Keep practising the current letters."],
+            Msg::ReadyToAdd => ["Pronto para juntar: ", "Ready to add: "],
+            Msg::DaysReading => ["Dias a ler", "Days reading"],
+            Msg::BackupsOf => ["Cópias de ", "Backups of "],
+            Msg::SaveBackup => ["Guardar cópia", "Save a backup"],
+            Msg::RestoreBackup => ["Recuperar cópia", "Restore a backup"],
+            Msg::MergeBackup => ["Juntar ao que já há", "Add to what is here"],
+            Msg::ReplaceAll => ["Substituir tudo", "Replace everything"],
+            Msg::BackupRestored => ["Cópia recuperada!", "Backup restored!"],
+            Msg::UnknownDifficulty => ["Esta dificuldade não existe. Escolhe outra!", "This difficulty does not exist. Pick another one!"],
+            Msg::EmptyFilter => ["Escolhe pelo menos uma letra!", "Pick at least one letter!"],
+            Msg::NoMatchingWords => ["Não há palavras com estas letras. Junta mais letras!", "There are no words with these letters. Add more letters!"],
+            Msg::WordlistNotLoaded => ["As palavras ainda não chegaram. Tenta outra vez daqui a pouco!", "The words have not arrived yet. Try again in a moment!"],
+            Msg::NoWordsInLanguage => ["Ainda não há palavras nesta língua. Escolhe outra!", "There are no words in this language yet. Pick another one!"],
+            Msg::EmptyName => ["Escreve um nome!", "Write a name!"],
+            Msg::DuplicateName => ["Já há um leitor com esse nome.", "There is already a reader with that name."],
+            Msg::UnknownProfile => ["Esse leitor já não existe.", "That reader is gone."],
+            Msg::NotABackup => ["Esse ficheiro não é uma cópia do Vamos Ler.", "That file is not a Let's Read backup."],
+        }
+    }
+
+    pub fn text(self, locale: Locale) -> &'static str {
+        let index = Locale::ALL.iter().position(|l| *l == locale).unwrap_or_default();
+        self.translations()[index]
+    }
+}
+
+/// How many words (or sentences) the setup screen's letters can draw from.
+pub fn matching_count_text(locale: Locale, count: usize, wanted: usize, reading_mode: &ReadingMode) -> String {
+    let items = match (locale, reading_mode) {
+        (Locale::Pt, ReadingMode::Words | ReadingMode::Spelling | ReadingMode::Recognition) => "palavras",
+        (Locale::Pt, ReadingMode::Sentences) => "frases",
+        (Locale::En, ReadingMode::Words | ReadingMode::Spelling | ReadingMode::Recognition) => "words",
+        (Locale::En, ReadingMode::Sentences) => "sentences",
+    };
+    match (locale, count) {
+        (Locale::Pt, 0) => format!("Não há {} com estas letras!", items),
+        (Locale::Pt, count) if count < wanted => format!("Só há {} {} com estas letras.", count, items),
+        (Locale::Pt, count) => format!("Há {} {} com estas letras.", count, items),
+        (Locale::En, 0) => format!("There are no {} with these letters!", items),
+        (Locale::En, count) if count < wanted => format!("There are only {} {} with these letters.", count, items),
+        (Locale::En, count) => format!("There are {} {} with these letters.", count, items),
+    }
+}
+
+/// The lesson being played and what it practises.
+pub fn lesson_text(locale: Locale, number: usize, focus: &[String]) -> String {
+    match locale {
+        Locale::Pt => format!("Lição {}: a treinar {}", number, focus.join(", ")),
+        Locale::En => format!("Lesson {}: practising {}", number, focus.join(", ")),
+    }
+}
+
+/// What a finished run read, and how fast.
+pub fn run_summary_text(locale: Locale, game_mode: &GameMode, words_read: usize, duration_secs: u32) -> String {
+    match (locale, game_mode) {
+        (Locale::Pt, GameMode::Classic) => format!("Leste {} palavras em {} segundos.", words_read, duration_secs),
+        (Locale::Pt, GameMode::TimeAttack) => format!("Leste {} palavras em {} segundos, contra o relógio.", words_read, duration_secs),
+        (Locale::En, GameMode::Classic) => format!("You read {} words in {} seconds.", words_read, duration_secs),
+        (Locale::En, GameMode::TimeAttack) => format!("You read {} words in {} seconds, against the clock.", words_read, duration_secs),
+    }
+}
+
+/// A run on the leaderboard.
+pub fn leaderboard_text(locale: Locale, words_per_minute: usize, words_read: usize) -> String {
+    match locale {
+        Locale::Pt => format!("{} palavras por minuto ({} palavras)", words_per_minute, words_read),
+        Locale::En => format!("{} words per minute ({} words)", words_per_minute, words_read),
+    }
+}
+
+/// How well a letter or digraph is read, e.g. "80% (8 of 10)".
+pub fn mastery_text(locale: Locale, percent: usize, read: usize, attempts: usize) -> String {
+    match locale {
+        Locale::Pt => format!("{}% ({} de {})", percent, read, attempts),
+        Locale::En => format!("{}% ({} of {})", percent, read, attempts),
+    }
+}
+
+/// A day of reading in the progress screen, before the picture of its hardest difficulty.
+pub fn day_summary_text(locale: Locale, date: &str, runs: usize, words_read: usize, best_words_per_minute: usize) -> String {
+    match locale {
+        Locale::Pt => format!("{}: {} jogos, {} palavras, até {} palavras por minuto ", date, runs, words_read, best_words_per_minute),
+        Locale::En => format!("{}: {} games, {} words, up to {} words per minute ", date, runs, words_read, best_words_per_minute),
+    }
+}
+
+/// What a backup about to be restored holds.
+pub fn backup_summary_text(locale: Locale, profile_name: &str, runs: usize, words_read: usize) -> String {
+    match locale {
+        Locale::Pt => format!("Cópia de {}: {} jogos e {} palavras lidas.", profile_name, runs, words_read),
+        Locale::En => format!("Backup of {}: {} games and {} words read.", profile_name, runs, words_read),
+    }
+}
+
+/// A backup written by a newer version of the app.
+pub fn newer_backup_text(locale: Locale, version: u32) -> String {
+    match locale {
+        Locale::Pt => format!("Essa cópia é de uma versão mais nova ({}), atualiza a aplicação.", version),
+        Locale::En => format!("That backup comes from a newer version ({}), update the app.", version),
+    }
+}
+
+/// A backup that cannot be restored, and why.
+pub fn damaged_backup_text(locale: Locale, reason: &str) -> String {
+    match locale {
+        Locale::Pt => format!("Essa cópia está estragada: {}", reason),
+        Locale::En => format!("That backup is damaged: {}", reason),
+    }
+}
+
+/// Why a backup is damaged: it reads a language the app does not know.
+pub fn unknown_language_text(locale: Locale, code: &str) -> String {
+    match locale {
+        Locale::Pt => format!("a língua {} não existe", code),
+        Locale::En => format!("there is no language {}", code),
+    }
+}
+
+/// Why a backup is damaged: it switches on a letter that is not on the keyboard.
+pub fn unknown_letter_text(locale: Locale, letter: &str) -> String {
+    match locale {
+        Locale::Pt => format!("a letra {} não existe no teclado", letter),
+        Locale::En => format!("the letter {} is not on the keyboard", letter),
+    }
+}
+
+/// Why a backup is damaged: it looks words up at an address that is not a web page.
+pub fn unsafe_dictionary_text(locale: Locale, template: &str) -> String {
+    match locale {
+        Locale::Pt => format!("o dicionário {} não é um endereço da internet", template),
+        Locale::En => format!("the dictionary {} is not a web address", template),
+    }
+}
+
+/// Cookie the locale shown on this device is copied to, so the server renders pages in it too.
+const LOCALE_COOKIE: &str = "locale";
+
+/// The locale picked on this device (shared by every profile), or the reader's preferred one until a locale is picked.
+#[derive(Clone, Copy)]
+pub struct StoredLocale {
+    pub locale: Signal<Locale>,
+    pub set_locale: WriteSignal<Option<Locale>>,
+}
+
+pub fn use_locale() -> StoredLocale {
+    let (picked, set_locale, _) = use_local_storage::<Option<Locale>, JsonCodec>("locale");
+    let preferred = preferred_locale();
+    StoredLocale { locale: Signal::derive(move || picked.get().unwrap_or(preferred)), set_locale }
+}
+
+/// While rendering on the server, the locale picked on the device, from its cookie, so the page matches what
+/// the hydrated app shows. Before a pick, the one asked for in the request's `Accept-Language`, which the
+/// browser builds from the same language list it hands to the hydrated app.
+#[cfg(feature = "ssr")]
+fn preferred_locale() -> Locale {
+    use_context::<actix_web::HttpRequest>()
+        .and_then(|req| {
+            let picked = req.cookie(LOCALE_COOKIE).and_then(|cookie| Locale::from_code(cookie.value()));
+            picked.or_else(|| req.headers().get(actix_web::http::header::ACCEPT_LANGUAGE)?.to_str().ok().and_then(Locale::from_accept_language))
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(not(feature = "ssr"))]
+fn preferred_locale() -> Locale {
+    let languages: Vec<String> = window().navigator().languages().iter().filter_map(|l| l.as_string()).collect();
+    Locale::from_accept_language(&languages.join(",")).unwrap_or_default()
+}
+
+fn save_locale_cookie(locale: Locale) {
+    use wasm_bindgen::JsCast;
+    if let Ok(document) = document().dyn_into::<web_sys::HtmlDocument>() {
+        let _ = document.set_cookie(&format!("{}={}; path=/; max-age=31536000; SameSite=Lax", LOCALE_COOKIE, locale.code()));
+    }
+}
+
+/// Switches the app's text between the translated locales.
+#[component]
+pub fn locale_picker() -> impl IntoView {
+    let StoredLocale { locale, set_locale } = use_locale();
+    // also covers locales picked before there was a cookie
+    create_effect(move |_| save_locale_cookie(locale.get()));
+
+    view! {
+        <div class="locale-picker">
+            {Locale::ALL.into_iter().map(|l| view! {
+                <span class="locale" class:active=move || locale.get() == l on:click=move |_| set_locale.set(Some(l))>{l.name()}</span>
+            }).collect_view()}
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_accept_language() {
+        assert_eq!(Locale::from_accept_language("en-GB,en;q=0.9,pt;q=0.8"), Some(Locale::En));
+        assert_eq!(Locale::from_accept_language("fr-FR, pt-PT;q=0.5, en;q=0.7"), Some(Locale::En), "highest quality wins, whatever the order");
+        assert_eq!(Locale::from_accept_language("pt-BR,en"), Some(Locale::Pt));
+        assert_eq!(Locale::from_accept_language("en;q=0,pt;q=0.1"), Some(Locale::Pt), "q=0 means not wanted");
+        assert_eq!(Locale::from_accept_language("fr,de"), None);
+        assert_eq!(Locale::from_accept_language(""), None);
+    }
+
+    #[test]
+    fn test_catalog() {
+        assert_eq!(Msg::Start.text(Locale::Pt), "COMEÇAR");
+        assert_eq!(Msg::Start.text(Locale::En), "START");
+        assert_eq!(matching_count_text(Locale::En, 3, 10, &ReadingMode::Sentences), "There are only 3 sentences with these letters.");
+        assert_eq!(matching_count_text(Locale::Pt, 0, 10, &ReadingMode::Words), "Não há palavras com estas letras!");
+        assert_eq!(run_summary_text(Locale::En, &GameMode::TimeAttack, 12, 60), "You read 12 words in 60 seconds, against the clock.");
+        assert_eq!(damaged_backup_text(Locale::En, &unknown_letter_text(Locale::En, "ç")), "That backup is damaged: the letter ç is not on the keyboard");
+        assert_eq!(Locale::from_code("en"), Some(Locale::En));
+        assert_eq!(Locale::from_code("fr"), None);
+    }
+}
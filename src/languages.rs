@@ -0,0 +1,240 @@
+use crate::syllables::Syllabifier;
+
+/// How long (in letters), how many syllables and how many hard digraphs a word may have at each difficulty.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Thresholds {
+    /// Shortest and longest word, in letters, for Easiest, Easy, Medium and Hard.
+    pub letters: [(usize, usize); 4],
+    /// Most syllables for Easiest, Easy and Medium, in languages with a syllabifier.
+    pub syllables: [usize; 3],
+    /// Most hard digraphs for Easiest and Easy.
+    pub digraphs: [usize; 2],
+    /// Hardest words are longer than this many letters...
+    pub hardest_letters: usize,
+    /// ...or longer than this many with two hard digraphs or more.
+    pub hardest_digraph_letters: usize,
+    /// Most words in a sentence for Easiest, Easy, Medium and Hard. Hardest sentences can be any length.
+    pub sentence_words: [usize; 4],
+}
+
+/// Everything that changes from one language to another: the keyboard, what makes a word hard and where to look words up.
+/// Each language also has its own wordlist, sentences and lessons, loaded by the server.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Language {
+    /// Short code, used in settings, server function arguments and file names, e.g. "pt".
+    pub code: &'static str,
+    /// Name shown in the language picker, in the language itself.
+    pub name: &'static str,
+    /// Letters on the setup keyboard, each with every variant (case and accents) it lets through the filter.
+    pub letter_groups: &'static [(&'static str, &'static str)],
+    /// Letter groups that make a single sound and trip up beginning readers.
+    pub hard_digraphs: &'static [&'static str],
+    pub thresholds: Thresholds,
+    /// Dictionary page of a word, with `{}` standing for the word.
+    pub dictionary_url: &'static str,
+    /// Language tag handed to the browser's speech synthesis.
+    pub speech_lang: &'static str,
+    /// Splits words into syllables. Without one, difficulties leave syllables out and words are not shown syllable by syllable.
+    pub syllabifier: Option<Syllabifier>,
+}
+
+pub const PORTUGUESE: Language = Language {
+    code: "pt",
+    name: "Português",
+    letter_groups: &[
+        ("A", "aAàÀáÁâÂãÃ"),
+        ("E", "eEèÈéÉêÊ"),
+        ("I", "iIíÍ"),
+        ("O", "oOôÔòÒóÓõÕ"),
+        ("U", "uUùÙúÚ"),
+        ("B", "bB"),
+        ("C", "cCÇ"),
+        ("D", "dD"),
+        ("F", "fF"),
+        ("G", "gG"),
+        ("H", "hH"),
+        ("J", "jJ"),
+        ("K", "kK"),
+        ("L", "lL"),
+        ("M", "mM"),
+        ("N", "nN"),
+        ("P", "pP"),
+        ("Q", "qQ"),
+        ("R", "rR"),
+        ("S", "sS"),
+        ("T", "tT"),
+        ("V", "vV"),
+        ("W", "wW"),
+        ("X", "xX"),
+        ("Y", "yY"),
+        ("Z", "zZ"),
+    ],
+    hard_digraphs: &["lh", "nh", "ch", "rr", "ss", "qu", "gu"],
+    thresholds: Thresholds {
+        letters: [(1, 5), (3, 7), (5, 9), (6, 11)],
+        syllables: [2, 3, 4],
+        digraphs: [0, 1],
+        hardest_letters: 10,
+        hardest_digraph_letters: 7,
+        sentence_words: [5, 6, 8, 10],
+    },
+    dictionary_url: "https://dicionario.priberam.org/{}",
+    speech_lang: "pt-PT",
+    syllabifier: Some(Syllabifier::Portuguese),
+};
+
+pub const ENGLISH: Language = Language {
+    code: "en",
+    name: "English",
+    letter_groups: &[
+        ("A", "aA"),
+        ("E", "eE"),
+        ("I", "iI"),
+        ("O", "oO"),
+        ("U", "uU"),
+        ("B", "bB"),
+        ("C", "cC"),
+        ("D", "dD"),
+        ("F", "fF"),
+        ("G", "gG"),
+        ("H", "hH"),
+        ("J", "jJ"),
+        ("K", "kK"),
+        ("L", "lL"),
+        ("M", "mM"),
+        ("N", "nN"),
+        ("P", "pP"),
+        ("Q", "qQ"),
+        ("R", "rR"),
+        ("S", "sS"),
+        ("T", "tT"),
+        ("V", "vV"),
+        ("W", "wW"),
+        ("X", "xX"),
+        ("Y", "yY"),
+        ("Z", "zZ"),
+    ],
+    hard_digraphs: &["th", "sh", "ch", "ph", "wh", "ck", "ng", "qu"],
+    thresholds: Thresholds {
+        letters: [(1, 4), (3, 6), (4, 8), (6, 11)],
+        syllables: [1, 2, 3],
+        digraphs: [0, 1],
+        hardest_letters: 10,
+        hardest_digraph_letters: 7,
+        sentence_words: [5, 6, 8, 10],
+    },
+    dictionary_url: "https://dictionary.cambridge.org/dictionary/english/{}",
+    speech_lang: "en-GB",
+    syllabifier: None,
+};
+
+/// Every language the app knows about. The server only plays the ones it was started with.
+pub const LANGUAGES: [&Language; 2] = [&PORTUGUESE, &ENGLISH];
+
+/// Played when a profile has not picked a language.
+pub const DEFAULT_LANGUAGE: &Language = &PORTUGUESE;
+
+/// The language with this code, or `None` if the app does not know it.
+pub fn find_language(code: &str) -> Option<&'static Language> {
+    LANGUAGES.into_iter().find(|l| l.code == code)
+}
+
+/// Labels of every key any language puts on the setup keyboard, each once, in keyboard order.
+/// Each profile keeps one switch per label, whichever language it is reading.
+pub fn keyboard_labels() -> Vec<&'static str> {
+    let mut labels: Vec<&'static str> = vec![];
+    for (label, _) in LANGUAGES.iter().flat_map(|l| l.letter_groups.iter()) {
+        if !labels.contains(label) {
+            labels.push(label);
+        }
+    }
+    labels
+}
+
+/// The language with this code, falling back to [`DEFAULT_LANGUAGE`] (settings saved before languages existed have no code).
+pub fn language(code: &str) -> &'static Language {
+    find_language(code).unwrap_or(DEFAULT_LANGUAGE)
+}
+
+/// Whether two codes stand for the same language, an empty code being the default one.
+pub fn same_language(a: &str, b: &str) -> bool {
+    language(a).code == language(b).code
+}
+
+impl Language {
+    /// Every variant the keyboard letter `letter` (in any case) stands for, or `None` if it is not on the keyboard.
+    pub fn letter_variants(&self, letter: &str) -> Option<&'static str> {
+        self.letter_groups.iter().find(|(label, _)| label.eq_ignore_ascii_case(letter)).map(|(_, variants)| *variants)
+    }
+
+    /// What a key of the spelling keyboard types, lowercase and each once: the plain letter first, then its accented variants.
+    pub fn key_variants(&self, letter: &str) -> Vec<char> {
+        let mut variants: Vec<char> = vec![];
+        for c in self.letter_variants(letter).unwrap_or_default().chars().flat_map(char::to_lowercase) {
+            if !variants.contains(&c) {
+                variants.push(c);
+            }
+        }
+        variants
+    }
+
+    /// The keyboard letter (lowercase) a character belongs to, accents included.
+    pub fn letter_of(&self, c: char) -> Option<String> {
+        self.letter_groups.iter().find(|(_, variants)| variants.contains(c)).map(|(label, _)| label.to_lowercase())
+    }
+
+    /// The letter filter the keyboard produces with exactly these letters switched on, in keyboard order.
+    pub fn allowed_chars_for(&self, letters: &[String]) -> String {
+        self.letter_groups
+            .iter()
+            .filter(|(label, _)| letters.iter().any(|l| label.eq_ignore_ascii_case(l)))
+            .map(|(_, variants)| *variants)
+            .collect()
+    }
+
+    /// The keyboard letters (lowercase) switched on to produce `allowed_chars`, the reverse of [`Language::allowed_chars_for`].
+    pub fn letters_in(&self, allowed_chars: &str) -> Vec<String> {
+        self.letter_groups
+            .iter()
+            .filter(|(_, variants)| allowed_chars.contains(variants))
+            .map(|(label, _)| label.to_lowercase())
+            .collect()
+    }
+
+    pub fn hard_digraph_count(&self, word: &str) -> usize {
+        let word = word.to_lowercase();
+        self.hard_digraphs.iter().map(|d| word.matches(d).count()).sum()
+    }
+
+    pub fn dictionary_link(&self, word: &str) -> String {
+        self.dictionary_url.replace("{}", &word.to_lowercase())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_language() {
+        assert_eq!(language("en"), &ENGLISH);
+        assert_eq!(language(""), &PORTUGUESE, "settings without a language play the default one");
+        assert_eq!(find_language("xx"), None);
+        assert_eq!(keyboard_labels().len(), 26, "both keyboards share their keys");
+
+        assert_eq!(PORTUGUESE.allowed_chars_for(&["p".to_string(), "a".to_string()]), "aAàÀáÁâÂãÃpP");
+        assert_eq!(ENGLISH.allowed_chars_for(&["p".to_string(), "a".to_string()]), "aApP");
+        assert_eq!(PORTUGUESE.letters_in("aAàÀáÁâÂãÃpP"), vec!["a", "p"]);
+        assert_eq!(PORTUGUESE.letter_variants("c"), Some("cCÇ"));
+        assert_eq!(PORTUGUESE.letter_variants("lh"), None);
+        assert_eq!(PORTUGUESE.key_variants("A"), vec!['a', 'à', 'á', 'â', 'ã']);
+        assert_eq!(PORTUGUESE.key_variants("c"), vec!['c', 'ç']);
+        assert_eq!(ENGLISH.key_variants("a"), vec!['a']);
+        assert_eq!(PORTUGUESE.letter_of('Ã'), Some("a".to_string()));
+        assert_eq!(ENGLISH.letter_of('ã'), None);
+
+        assert_eq!(PORTUGUESE.hard_digraph_count("palhinha"), 2);
+        assert_eq!(ENGLISH.hard_digraph_count("Thick"), 2);
+        assert_eq!(PORTUGUESE.dictionary_link("Pato"), "https://dicionario.priberam.org/pato");
+    }
+}
@@ -1,82 +1,472 @@
-use std::fs::File;
-use std::io::{prelude::*, BufReader};
 use leptos::{server, ServerFnError};
 use regex::Regex;
-use rand::{seq::{IteratorRandom}, thread_rng};
-use std::collections::HashMap;
-use rand::prelude::SliceRandom;
-use crate::app::Difficulty;
+use rand::{seq::{IteratorRandom, SliceRandom}, thread_rng, Rng};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+use crate::app::{Difficulty, ReadingMode};
+use crate::i18n::{Locale, Msg};
+use crate::languages::Language;
+use crate::pictures;
 use cfg_if::cfg_if;
+use serde::{Serialize, Deserialize};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Why a word or sentence pool could not be put together.
+/// Travels from the server functions as a `ServerFnError::ServerError` holding the error code.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WordPoolError {
+    UnknownDifficulty,
+    EmptyFilter,
+    NoMatchingWords,
+    WordlistNotLoaded,
+    UnknownLanguage,
+}
+
+const WORD_POOL_ERROR_PREFIX: &str = "word_pool_error:";
+
+impl WordPoolError {
+    /// Message to show the child, in the app's locale.
+    pub fn message(&self, locale: Locale) -> &'static str {
+        let msg = match self {
+            WordPoolError::UnknownDifficulty => Msg::UnknownDifficulty,
+            WordPoolError::EmptyFilter => Msg::EmptyFilter,
+            WordPoolError::NoMatchingWords => Msg::NoMatchingWords,
+            WordPoolError::WordlistNotLoaded => Msg::WordlistNotLoaded,
+            WordPoolError::UnknownLanguage => Msg::NoWordsInLanguage,
+        };
+        msg.text(locale)
+    }
+
+    /// Recovers the typed error from what a server function returned, if it was one.
+    pub fn from_server_fn_error(error: &ServerFnError) -> Option<WordPoolError> {
+        match error {
+            ServerFnError::ServerError(code) => code.parse().ok(),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for WordPoolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let code = match self {
+            WordPoolError::UnknownDifficulty => "unknown_difficulty",
+            WordPoolError::EmptyFilter => "empty_filter",
+            WordPoolError::NoMatchingWords => "no_matching_words",
+            WordPoolError::WordlistNotLoaded => "wordlist_not_loaded",
+            WordPoolError::UnknownLanguage => "unknown_language",
+        };
+        write!(f, "{}{}", WORD_POOL_ERROR_PREFIX, code)
+    }
+}
+
+impl FromStr for WordPoolError {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix(WORD_POOL_ERROR_PREFIX) {
+            Some("unknown_difficulty") => Ok(WordPoolError::UnknownDifficulty),
+            Some("empty_filter") => Ok(WordPoolError::EmptyFilter),
+            Some("no_matching_words") => Ok(WordPoolError::NoMatchingWords),
+            Some("wordlist_not_loaded") => Ok(WordPoolError::WordlistNotLoaded),
+            Some("unknown_language") => Ok(WordPoolError::UnknownLanguage),
+            _ => Err(()),
+        }
+    }
+}
+
+impl From<WordPoolError> for ServerFnError {
+    fn from(error: WordPoolError) -> Self {
+        ServerFnError::ServerError(error.to_string())
+    }
+}
+
+/// A word (or sentence) handed to the client, already split into syllables so it can be shown syllable by syllable.
+/// Words of languages without a syllabifier come unsplit, with no syllables.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PoolWord {
+    pub text: String,
+    pub syllables: Vec<String>,
+    /// Url of the word's picture, only filled in when pictures were asked for.
+    #[serde(default)]
+    pub picture: Option<String>,
+    /// In recognition questions, the words to pick this one from, itself included, in the order shown.
+    #[serde(default)]
+    pub choices: Vec<String>,
+}
+
+impl PoolWord {
+    pub fn new(language: &Language, text: &str) -> Self {
+        PoolWord {
+            text: text.to_string(),
+            syllables: language.syllabifier.map(|s| s.syllabify(text)).unwrap_or_default(),
+            picture: None,
+            choices: vec![],
+        }
+    }
+}
+
+/// What a word pool favours, besides fitting the letters.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PoolPreferences<'a> {
+    /// Letters and digraphs of the lesson being played, for two thirds of the pool.
+    pub focus: &'a [String],
+    /// Words due for review, most urgent first, for up to half of the pool.
+    pub review: &'a [String],
+    /// Picture paths by lowercase word. When given, words with a picture come before the others and carry it.
+    pub pictures: Option<&'a BTreeMap<String, String>>,
+}
+
+fn sanitize_filter(chars: &String) -> String {
+    chars.chars().filter( |c| c.is_alphabetic()).collect()
+}
+
+fn matching_words<'a>(words: &'a [String], allowed_regex: Option<&'a Regex>) -> impl Iterator<Item = &'a String> {
+    words.iter().filter(move |w| allowed_regex.is_none_or(|r| r.is_match(w)))
+}
+
+fn matching_sentences<'a>(language: &'a Language, sentences: &'a [String], diff: &'a Difficulty, allowed_regex: Option<&'a Regex>) -> impl Iterator<Item = &'a String> {
+    sentences.iter().filter(move |s| allowed_sentence(language, s, diff, allowed_regex))
+}
+
+/// Whether a word has one of the letters or digraphs a lesson is practising. Single letters match any of their accented variants.
+fn has_focus(language: &Language, word: &str, focus: &[String]) -> bool {
+    let lowercase = word.to_lowercase();
+    focus.iter().any(|f| match language.letter_variants(f) {
+        Some(variants) => word.chars().any(|c| variants.contains(c)),
+        None => lowercase.contains(&f.to_lowercase()),
+    })
+}
+
+/// Picks `amount` words, two thirds of them (when there are enough) having one of the `focus` letters or digraphs.
+fn choose_with_focus<'a, R: Rng>(language: &Language, words: impl Iterator<Item = &'a String>, focus: &[String], amount: usize, rng: &mut R) -> Vec<&'a String> {
+    let (focused, others): (Vec<&String>, Vec<&String>) = words.partition(|w| has_focus(language, w, focus));
+    let focused_amount = match focus.is_empty() {
+        true => 0,
+        false => (amount * 2).div_ceil(3).max(amount.saturating_sub(others.len())),
+    };
+
+    let mut pool: Vec<&String> = focused.into_iter().choose_multiple(rng, focused_amount);
+    pool.extend(others.into_iter().choose_multiple(rng, amount - pool.len()));
+    pool.shuffle(rng);
+    pool
+}
+
+/// Takes the `review` words found among the candidates, in the order given, for up to half of a pool of `amount`.
+/// Returns them along with the candidates left to fill the rest of the pool.
+fn split_reviews<'a>(candidates: impl Iterator<Item = &'a String>, review: &[String], amount: usize) -> (Vec<&'a String>, Vec<&'a String>) {
+    let due: HashSet<&str> = review.iter().map(|r| r.as_str()).collect();
+    let (due_candidates, rest): (Vec<&String>, Vec<&String>) = candidates.partition(|c| due.contains(c.as_str()));
+
+    let reviews: Vec<&String> = review
+        .iter()
+        .filter_map(|r| due_candidates.iter().find(|c| *c == &r).copied())
+        .take(amount.div_ceil(2))
+        .collect();
+    let rest = due_candidates.into_iter().filter(|c| !reviews.contains(c)).chain(rest).collect();
+    (reviews, rest)
+}
+
+fn allowed_chars_regex(chars: &String) -> Result<Regex, WordPoolError> {
+    match sanitize_filter(chars) {
+        sanitized if sanitized.is_empty() => Err(WordPoolError::EmptyFilter),
+        sanitized => Regex::new(format!("^[{}]+$", sanitized).as_str()).map_err(|_| WordPoolError::EmptyFilter),
+    }
+}
+
+/// Puts together a pool of up to `num_words` words from `words` (the words of one difficulty), starting with the `review`
+/// words that pass the letter filter (up to half the pool) and preferring words with one of the `focus` letters or digraphs for the rest.
+/// With `pictures`, the rest is drawn from words that have a picture first, and only topped up with the others.
+pub fn word_pool<R: Rng>(language: &Language, words: &[String], allowed_chars: Option<String>, num_words: usize, preferences: &PoolPreferences, rng: &mut R) -> Result<Vec<PoolWord>, WordPoolError> {
+    let allowed_regex = allowed_chars.map(|chars| allowed_chars_regex(&chars)).transpose()?;
+    let (mut chosen, rest) = split_reviews(matching_words(words, allowed_regex.as_ref()), preferences.review, num_words);
+    let focus = preferences.focus;
+    match preferences.pictures {
+        Some(pictures) => {
+            let (pictured, others): (Vec<&String>, Vec<&String>) = rest.into_iter().partition(|w| pictures.contains_key(&w.to_lowercase()));
+            chosen.extend(choose_with_focus(language, pictured.into_iter(), focus, num_words - chosen.len(), rng));
+            chosen.extend(choose_with_focus(language, others.into_iter(), focus, num_words - chosen.len(), rng));
+        },
+        None => chosen.extend(choose_with_focus(language, rest.into_iter(), focus, num_words - chosen.len(), rng)),
+    }
+    chosen.shuffle(rng);
+    let pool: Vec<PoolWord> = chosen
+        .into_iter()
+        .map(|s| PoolWord {
+            picture: preferences.pictures.and_then(|p| p.get(&s.to_lowercase())).map(|path| pictures::picture_url(path)),
+            ..PoolWord::new(language, s)
+        })
+        .collect();
+
+    match pool.is_empty() {
+        true => Err(WordPoolError::NoMatchingWords),
+        false => Ok(pool),
+    }
+}
+
+/// How many words a recognition question offers to pick from: the right one and three distractors.
+pub const CHOICES_PER_QUESTION: usize = 4;
+
+/// Words of about the same length look alike: 1-2 letters, 3-4, 5-6 and so on.
+fn length_bucket(w: &str) -> usize {
+    letter_count(w).div_ceil(2)
+}
+
+/// How alike two words look: the letters they share (accents aside), plus the ones in the same place.
+fn similarity(language: &Language, a: &str, b: &str) -> usize {
+    let letters = |w: &str| -> Vec<String> { w.chars().map(|c| language.letter_of(c).unwrap_or_else(|| c.to_lowercase().to_string())).collect() };
+    let (a, b) = (letters(a), letters(b));
+
+    let mut unmatched = b.clone();
+    let shared = a
+        .iter()
+        .filter(|l| match unmatched.iter().position(|u| u == *l) {
+            Some(i) => { unmatched.swap_remove(i); true },
+            None => false,
+        })
+        .count();
+    let in_place = a.iter().zip(b.iter()).filter(|(x, y)| x == y).count();
+    shared + in_place
+}
+
+/// Picks `amount` words from `candidates` that are easy to mistake for `word`: the same length bucket first,
+/// then the most letters in common. They are drawn at random from the best few, so a word does not always come with the same ones.
+fn distractors<'a, R: Rng>(language: &Language, word: &str, candidates: &[&'a String], amount: usize, rng: &mut R) -> Vec<&'a String> {
+    let word_lowercase = word.to_lowercase();
+    let mut ranked: Vec<(bool, usize, &'a String)> = candidates
+        .iter()
+        .filter(|c| c.to_lowercase() != word_lowercase)
+        .map(|c| (length_bucket(c) == length_bucket(word), similarity(language, word, c), *c))
+        .collect();
+    ranked.sort_by_key(|(same_bucket, score, _)| Reverse((*same_bucket, *score)));
+
+    let mut best: Vec<&String> = vec![];
+    for (_, _, c) in ranked {
+        if !best.iter().any(|b| b.to_lowercase() == c.to_lowercase()) {
+            best.push(c);
+        }
+        if best.len() == amount * 2 {
+            break;
+        }
+    }
+    best.into_iter().choose_multiple(rng, amount)
+}
+
+/// Puts together recognition questions: words drawn like [`word_pool`], each with distractors from the
+/// same words (so of the same difficulty and letters) to pick it from, `num_choices` words in all.
+pub fn question_pool<R: Rng>(language: &Language, words: &[String], allowed_chars: Option<String>, num_questions: usize, num_choices: usize, preferences: &PoolPreferences, rng: &mut R) -> Result<Vec<PoolWord>, WordPoolError> {
+    let mut pool = word_pool(language, words, allowed_chars.clone(), num_questions, preferences, rng)?;
+    let allowed_regex = allowed_chars.map(|chars| allowed_chars_regex(&chars)).transpose()?;
+    let candidates: Vec<&String> = matching_words(words, allowed_regex.as_ref()).collect();
+
+    for question in pool.iter_mut() {
+        let mut choices: Vec<String> = distractors(language, &question.text, &candidates, num_choices.saturating_sub(1), rng).into_iter().cloned().collect();
+        choices.push(question.text.clone());
+        choices.shuffle(rng);
+        question.choices = choices;
+    }
+    Ok(pool)
+}
+
+/// Like [`word_pool`], for sentences that fit `diff`.
+pub fn sentence_pool<R: Rng>(language: &Language, sentences: &[String], allowed_chars: Option<String>, num_sentences: usize, diff: &Difficulty, review: &[String], rng: &mut R) -> Result<Vec<PoolWord>, WordPoolError> {
+    let allowed_regex = allowed_chars.map(|chars| allowed_chars_regex(&chars)).transpose()?;
+    let (mut chosen, rest) = split_reviews(matching_sentences(language, sentences, diff, allowed_regex.as_ref()), review, num_sentences);
+    chosen.extend(rest.into_iter().choose_multiple(rng, num_sentences - chosen.len()));
+    chosen.shuffle(rng);
+    let pool: Vec<PoolWord> = chosen.into_iter().map(|s| PoolWord::new(language, s)).collect();
+
+    match pool.is_empty() {
+        true => Err(WordPoolError::NoMatchingWords),
+        false => Ok(pool),
+    }
+}
+
+/// How many of `words` (or `sentences`, depending on the reading mode) pass the filters. A filter without letters matches nothing.
+pub fn matching_count(language: &Language, words: &[String], sentences: &[String], allowed_chars: Option<String>, diff: &Difficulty, reading_mode: &ReadingMode) -> Result<usize, WordPoolError> {
+    let allowed_regex = match allowed_chars.map(|chars| allowed_chars_regex(&chars)).transpose() {
+        Ok(allowed_regex) => allowed_regex,
+        Err(WordPoolError::EmptyFilter) => return Ok(0),
+        Err(e) => return Err(e),
+    };
+
+    match reading_mode {
+        ReadingMode::Words | ReadingMode::Spelling | ReadingMode::Recognition => Ok(matching_words(words, allowed_regex.as_ref()).count()),
+        ReadingMode::Sentences => Ok(matching_sentences(language, sentences, diff, allowed_regex.as_ref()).count()),
+    }
+}
 
 cfg_if! {
     if #[cfg(feature = "ssr")] {
+        use actix_web::web::Data;
         use leptos_actix::extract;
-        
-        fn sanitize_filter(chars: &String) -> String {
-            chars.chars().filter( |c| c.is_alphabetic()).collect()
-        }
-        
-        pub fn load_words(filename: &str) -> HashMap<Difficulty, Vec<String>> {
-            let f: File = File::open(filename).expect("couldnt open file");
-            let reader = BufReader::new(f);
+        use crate::curriculum::Lesson;
+        use crate::languages;
+
+        /// Sorts words into the difficulties they can be played at. A word can fit more than one difficulty.
+        pub fn classify_words(language: &Language, words: Vec<String>) -> HashMap<Difficulty, Vec<String>> {
             let mut words_per_diff: HashMap<Difficulty, Vec<String>> = HashMap::new();
             
-            for d in [Difficulty::Easiest, Difficulty::Easy, Difficulty::Medium, Difficulty::Hard, Difficulty::Hardest].iter() {
+            for d in Difficulty::ALL.iter() {
                 words_per_diff.insert(d.clone(), vec![]);
             }
             
-            for l in reader.lines().flat_map( |maybe_l| maybe_l.ok()) {
-                for d in [Difficulty::Easiest, Difficulty::Easy, Difficulty::Medium, Difficulty::Hard, Difficulty::Hardest].iter().filter(|d| allowed_difficulty(&l, d)) {
+            for l in words {
+                for d in Difficulty::ALL.iter().filter(|d| allowed_difficulty(language, &l, d)) {
                     words_per_diff.get_mut(d).unwrap().push(l.clone());
                 }
             }
         
             words_per_diff
         }
+
+        /// Words, sentences (one per line of the corpus file), lessons and definitions of one language, loaded at startup.
+        pub struct LanguageData {
+            pub language: &'static Language,
+            pub words: HashMap<Difficulty, Vec<String>>,
+            pub sentences: Vec<String>,
+            pub lessons: Vec<Lesson>,
+            /// Lowercase word to definition, empty without a definitions file.
+            pub definitions: BTreeMap<String, String>,
+            /// Lowercase word to picture path under `assets/`, empty without a picture manifest.
+            pub pictures: BTreeMap<String, String>,
+        }
+
+        /// Every language the server was started with, by code.
+        pub struct Library(pub HashMap<String, LanguageData>);
+
+        impl Library {
+            pub fn get(&self, code: &str) -> Result<&LanguageData, WordPoolError> {
+                self.0.get(code).ok_or(WordPoolError::UnknownLanguage)
+            }
+
+            /// Codes of the loaded languages, the default one first.
+            pub fn codes(&self) -> Vec<String> {
+                let mut codes: Vec<String> = self.0.keys().cloned().collect();
+                codes.sort_by_key(|c| (c != languages::DEFAULT_LANGUAGE.code, c.clone()));
+                codes
+            }
+        }
+
+        pub async fn library() -> Result<Data<Library>, WordPoolError> {
+            extract(|library: Data<Library>| async move { library })
+                .await
+                .map_err(|_| WordPoolError::WordlistNotLoaded)
+        }
     }
 }
         
+/// Draws a pool of words, starting with the `review` words that fit the letters and difficulty (up to half the pool),
+/// and preferring words with one of the `focus` letters or digraphs (the ones a lesson just introduced) for the rest.
+/// `with_pictures` prefers words that have a picture, and hands out their pictures.
 #[server]
-pub async fn get_word_pool(allowed_chars: Option<String>, num_words: usize, diff: Difficulty) -> Result<Vec<String>, ServerFnError> {
-    let words_data = extract!(actix_web::web::Data<HashMap<Difficulty, Vec<String>>>);
-        let words = words_data.get(&diff).unwrap();
-        
-        Ok(match allowed_chars {
-            None => 
-                words
-                    .choose_multiple(&mut thread_rng(), num_words)
-                    .map(|s| s.clone())
-                    .collect(),
-            Some(chars) => {
-                let allowed_regex = Regex::new(format!("^[{}]+$", sanitize_filter(&chars)).as_str()).unwrap();
-                words
-                    .iter()
-                    .filter(|w| allowed_regex.is_match(w))
-                    .choose_multiple(&mut thread_rng(), num_words)
-                    .into_iter()
-                    .map(|s| s.clone())
-                    .collect()
-                },
+pub async fn get_word_pool(language: String, allowed_chars: Option<String>, num_words: usize, diff: Difficulty, focus: Vec<String>, review: Vec<String>, with_pictures: bool) -> Result<Vec<PoolWord>, ServerFnError> {
+    let library = library().await?;
+    let data = library.get(&language)?;
+    let words = data.words.get(&diff).ok_or(WordPoolError::UnknownDifficulty)?;
+    let preferences = PoolPreferences { focus: &focus, review: &review, pictures: with_pictures.then_some(&data.pictures) };
+    Ok(word_pool(data.language, words, allowed_chars, num_words, &preferences, &mut thread_rng())?)
+}
+
+/// Draws recognition questions: words picked like [`get_word_pool`] does, each coming with distractors of the same
+/// difficulty that look like it, [`CHOICES_PER_QUESTION`] words in all, in the order to show them.
+#[server]
+pub async fn get_questions(language: String, allowed_chars: Option<String>, num_questions: usize, diff: Difficulty, focus: Vec<String>, review: Vec<String>, with_pictures: bool) -> Result<Vec<PoolWord>, ServerFnError> {
+    let library = library().await?;
+    let data = library.get(&language)?;
+    let words = data.words.get(&diff).ok_or(WordPoolError::UnknownDifficulty)?;
+    let preferences = PoolPreferences { focus: &focus, review: &review, pictures: with_pictures.then_some(&data.pictures) };
+    Ok(question_pool(data.language, words, allowed_chars, num_questions, CHOICES_PER_QUESTION, &preferences, &mut thread_rng())?)
+}
+
+#[server]
+pub async fn get_sentence_pool(language: String, allowed_chars: Option<String>, num_sentences: usize, diff: Difficulty, review: Vec<String>) -> Result<Vec<PoolWord>, ServerFnError> {
+    let library = library().await?;
+    let data = library.get(&language)?;
+    Ok(sentence_pool(data.language, &data.sentences, allowed_chars, num_sentences, &diff, &review, &mut thread_rng())?)
+}
+
+/// How many words (or sentences) a pool with these settings can be drawn from, so the setup screen can
+/// warn before starting a run that would come up short. A filter without letters matches nothing.
+#[server]
+pub async fn count_matching(language: String, allowed_chars: Option<String>, diff: Difficulty, reading_mode: ReadingMode) -> Result<usize, ServerFnError> {
+    let library = library().await?;
+    let data = library.get(&language)?;
+    let words = data.words.get(&diff).ok_or(WordPoolError::UnknownDifficulty)?;
+    Ok(matching_count(data.language, words, &data.sentences, allowed_chars, &diff, &reading_mode)?)
+}
+
+/// The languages the server has words for, by code, the default one first.
+#[server]
+pub async fn get_languages() -> Result<Vec<String>, ServerFnError> {
+    Ok(library().await?.codes())
+}
+
+/// Splits a sentence into the words that have to pass the letter filter, dropping punctuation.
+fn sentence_words(sentence: &str) -> impl Iterator<Item = &str> {
+    sentence
+        .split(|c: char| !(c.is_alphabetic() || c == '-'))
+        .filter(|w| !w.is_empty())
+}
+
+fn max_sentence_words(language: &Language, diff: &Difficulty) -> usize {
+    let limits = language.thresholds.sentence_words;
+    match diff {
+        Difficulty::Easiest => limits[0],
+        Difficulty::Easy => limits[1],
+        Difficulty::Medium => limits[2],
+        Difficulty::Hard => limits[3],
+        Difficulty::Hardest => usize::MAX,
+    }
+}
+
+/// A sentence fits a difficulty when it is short enough and every word in it would be
+/// picked for that difficulty or an easier one (so "o" and "a" still show up in harder sentences).
+fn allowed_sentence(language: &Language, sentence: &str, diff: &Difficulty, allowed_regex: Option<&Regex>) -> bool {
+    let words: Vec<&str> = sentence_words(sentence).collect();
+
+    !words.is_empty()
+        && words.len() <= max_sentence_words(language, diff)
+        && words.iter().all(|w| {
+            let w = w.to_string();
+            allowed_regex.is_none_or(|r| r.is_match(&w))
+                && Difficulty::ALL.iter().filter(|d| *d <= diff).any(|d| allowed_difficulty(language, &w, d))
         })
 }
 
+/// Length of a word as a reader sees it: "pá" is 2 letters even though "á" takes 2 bytes (or 2 chars when decomposed).
+fn letter_count(w: &str) -> usize {
+    w.graphemes(true).count()
+}
+
+/// First pass is the length in letters, second pass keeps words with many syllables or hard digraphs
+/// out of the easier levels even when they are short (chá, carro, baía). The limits come from the language,
+/// and syllables only count in languages with a syllabifier.
+fn allowed_difficulty(language: &Language, w: &String, diff: &Difficulty) -> bool {
+    let len = letter_count(w);
+    let hyphens = w.matches("-").count();
+    let digraphs = language.hard_digraph_count(w);
+    let syllables = language.syllabifier.map(|s| s.syllable_count(w));
+    let t = &language.thresholds;
+    let fits_length = |i: usize| len >= t.letters[i].0 && len <= t.letters[i].1;
+    let fits_syllables = |i: usize| syllables.is_none_or(|n| n <= t.syllables[i]);
 
-fn allowed_difficulty(w: &String, diff: &Difficulty) -> bool {
     match diff {
         Difficulty::Easiest => {
-            w.len() < 6 && w.rfind("-").is_none()
+            fits_length(0) && hyphens == 0 && digraphs <= t.digraphs[0] && fits_syllables(0)
         },
         Difficulty::Easy => {
-            w.len() < 8 && w.len() > 2 && w.rfind("-").is_none()
+            fits_length(1) && hyphens == 0 && digraphs <= t.digraphs[1] && fits_syllables(1)
         },
         Difficulty::Medium => {
-            w.len() < 10 && w.len() > 4 && w.rfind("-").is_none()
+            fits_length(2) && hyphens == 0 && fits_syllables(2)
         },
         Difficulty::Hard => {
-            w.len() < 12 && w.len() > 5 && w.matches("-").collect::<Vec<&str>>().len() < 2
+            fits_length(3) && hyphens < 2
         },
         Difficulty::Hardest => {
-            w.len() > 10
+            len > t.hardest_letters || (len > t.hardest_digraph_letters && digraphs >= 2)
         },
     }
 }
@@ -84,6 +474,7 @@ fn allowed_difficulty(w: &String, diff: &Difficulty) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::languages::{ENGLISH, PORTUGUESE};
 
     #[test]
     fn test_sanitize_filter() {
@@ -94,32 +485,188 @@ mod tests {
         
     }
 
+    #[test]
+    fn test_allowed_chars_regex() {
+        assert_eq!(allowed_chars_regex(&"".to_owned()).err(), Some(WordPoolError::EmptyFilter));
+        assert_eq!(allowed_chars_regex(&"24'; -".to_owned()).err(), Some(WordPoolError::EmptyFilter));
+        assert!(allowed_chars_regex(&"pPaA".to_owned()).unwrap().is_match("papa"));
+    }
+
+    #[test]
+    fn test_word_pool_error_round_trip() {
+        for error in [WordPoolError::UnknownDifficulty, WordPoolError::EmptyFilter, WordPoolError::NoMatchingWords, WordPoolError::WordlistNotLoaded, WordPoolError::UnknownLanguage] {
+            assert_eq!(WordPoolError::from_server_fn_error(&error.clone().into()), Some(error));
+        }
+        assert_eq!(WordPoolError::from_server_fn_error(&ServerFnError::ServerError("500 Internal Server Error".to_owned())), None);
+        assert_eq!(WordPoolError::from_server_fn_error(&ServerFnError::Request("offline".to_owned())), None);
+    }
+
+    #[test]
+    fn test_choose_with_focus() {
+        let words: Vec<String> = ["pato", "pipa", "Pêra", "tia", "ia", "ai", "lua", "tatu", "mala", "olá"].iter().map(|w| w.to_string()).collect();
+        let focus = vec!["p".to_string()];
+        let count_focused = |pool: &[&String]| pool.iter().filter(|w| has_focus(&PORTUGUESE, w, &focus)).count();
+
+        let pool = choose_with_focus(&PORTUGUESE, words.iter(), &focus, 6, &mut thread_rng());
+        assert_eq!(pool.len(), 6);
+        assert_eq!(count_focused(&pool), 3, "there are only 3 words with p, Pêra included");
+
+        let pool = choose_with_focus(&PORTUGUESE, words.iter().skip(1), &focus, 3, &mut thread_rng());
+        assert_eq!(count_focused(&pool), 2);
+
+        let pool = choose_with_focus(&PORTUGUESE, words.iter(), &[], 4, &mut thread_rng());
+        assert_eq!(pool.len(), 4);
+
+        assert!(has_focus(&PORTUGUESE, "palhinha", &["lh".to_string()]));
+        assert!(!has_focus(&PORTUGUESE, "pato", &["lh".to_string()]));
+    }
+
+    #[test]
+    fn test_split_reviews() {
+        let words: Vec<String> = ["pato", "pipa", "tia", "lua", "tatu"].iter().map(|w| w.to_string()).collect();
+        let review: Vec<String> = ["chá", "tatu", "tia", "pato"].iter().map(|w| w.to_string()).collect();
+
+        let (reviews, rest) = split_reviews(words.iter(), &review, 4);
+        assert_eq!(reviews, vec!["tatu", "tia"], "review words that do not fit the filters are skipped, and at most half the pool reviews");
+        assert_eq!(rest, vec!["pato", "pipa", "lua"]);
+
+        let (reviews, rest) = split_reviews(words.iter(), &[], 4);
+        assert!(reviews.is_empty());
+        assert_eq!(rest.len(), 5);
+    }
+
+    #[test]
+    fn test_word_pool() {
+        let words: Vec<String> = ["pato", "pipa", "tia", "lua"].iter().map(|w| w.to_string()).collect();
+        let review = vec!["lua".to_string()];
+
+        let reviewing = PoolPreferences { review: &review, ..Default::default() };
+
+        let pool = word_pool(&PORTUGUESE, &words, Some("aAuUlL".to_string()), 3, &reviewing, &mut thread_rng()).unwrap();
+        assert_eq!(pool, vec![PoolWord::new(&PORTUGUESE, "lua")], "only lua fits the letters");
+        assert_eq!(word_pool(&PORTUGUESE, &words, None, 3, &reviewing, &mut thread_rng()).map(|p| p.len()), Ok(3));
+        assert_eq!(word_pool(&PORTUGUESE, &words, Some("24".to_string()), 3, &Default::default(), &mut thread_rng()), Err(WordPoolError::EmptyFilter));
+        assert_eq!(word_pool(&PORTUGUESE, &words, Some("xX".to_string()), 3, &Default::default(), &mut thread_rng()), Err(WordPoolError::NoMatchingWords));
+        assert_eq!(matching_count(&PORTUGUESE, &words, &[], Some("24".to_string()), &Difficulty::Easy, &ReadingMode::Words), Ok(0));
+    }
+
+    #[test]
+    fn test_word_pool_with_pictures() {
+        let words: Vec<String> = ["pato", "pipa", "tia", "lua", "sol"].iter().map(|w| w.to_string()).collect();
+        let pictures = BTreeMap::from([("pato".to_string(), "pictures/pato.svg".to_string()), ("sol".to_string(), "pictures/sol.svg".to_string())]);
+        let with_pictures = PoolPreferences { pictures: Some(&pictures), ..Default::default() };
+
+        let mut pool = word_pool(&PORTUGUESE, &words, None, 2, &with_pictures, &mut thread_rng()).unwrap();
+        pool.sort_by(|a, b| a.text.cmp(&b.text));
+        assert_eq!(pool.iter().map(|w| w.picture.as_deref()).collect::<Vec<_>>(), vec![Some("/assets/pictures/pato.svg"), Some("/assets/pictures/sol.svg")]);
+
+        let pool = word_pool(&PORTUGUESE, &words, None, 4, &with_pictures, &mut thread_rng()).unwrap();
+        assert_eq!(pool.iter().filter(|w| w.picture.is_some()).count(), 2, "topped up with words without a picture");
+        let pool = word_pool(&PORTUGUESE, &words, None, 5, &Default::default(), &mut thread_rng()).unwrap();
+        assert!(pool.iter().all(|w| w.picture.is_none()), "pictures only travel when asked for");
+    }
+
+    #[test]
+    fn test_distractors() {
+        let words: Vec<String> = ["pato", "pata", "pão", "gato", "mala", "Pato", "bicicleta", "tio"].iter().map(|w| w.to_string()).collect();
+        let candidates: Vec<&String> = words.iter().collect();
+
+        let picked = distractors(&PORTUGUESE, "pato", &candidates, 1, &mut thread_rng());
+        assert!(picked == vec!["pata"] || picked == vec!["gato"], "{:?} is not among the most alike", picked);
+        assert!(similarity(&PORTUGUESE, "pão", "pao") > similarity(&PORTUGUESE, "pão", "pia"), "accents aside, the letters are the same");
+        assert_eq!(distractors(&PORTUGUESE, "pato", &candidates, 10, &mut thread_rng()).len(), 6, "the word itself is left out, in any case");
+    }
+
+    #[test]
+    fn test_question_pool() {
+        let words: Vec<String> = ["pato", "pata", "gato", "mala", "tia", "lua"].iter().map(|w| w.to_string()).collect();
+
+        let questions = question_pool(&PORTUGUESE, &words, Some("pPaAtTiIoO".to_string()), 2, 3, &Default::default(), &mut thread_rng()).unwrap();
+        assert_eq!(questions.len(), 2);
+        for q in questions.iter() {
+            assert_eq!(q.choices.len(), 3);
+            assert!(q.choices.contains(&q.text));
+            assert!(q.choices.iter().all(|c| ["pato", "pata", "tia"].contains(&c.as_str())), "distractors fit the letters too");
+        }
+    }
+
+    fn allowed_at(words: &[String], diff: Difficulty) -> Vec<String> {
+        let mut allowed: Vec<String> = words.iter().filter(|w| allowed_difficulty(&PORTUGUESE, w, &diff)).cloned().collect();
+        allowed.sort();
+        allowed
+    }
+
+    fn sorted(words: &[&str]) -> Vec<String> {
+        let mut words: Vec<String> = words.iter().map(|w| w.to_string()).collect();
+        words.sort();
+        words
+    }
+
     #[test]
     fn test_difficulty() {
-        let easiest_words = [ "papa".to_owned(), "ai".to_owned() ];
-        let easy_words = [ "batata".to_owned(), "resolve".to_owned(), "papa".to_owned(), "pá".to_owned()]; //pá is len() == 3 because á is 2 unicode chars
-        let medium_words = [ "impotente".to_owned(), "alarvará".to_owned(), "batata".to_owned(), "resolve".to_owned() ];
-        let hard_words = [ "hipotético".to_owned(), "pô-los".to_owned() ,"impotente".to_owned(), "alarvará".to_owned(), "batata".to_owned(), "resolve".to_owned()];
-        let hardest_words = [ "anticonstitucionalissimamente".to_owned(), "aâæãée-24ēçćc-bbò".to_owned() ];
+        let easiest_words = sorted(&[ "papa", "ai", "pá", "tia" ]); //pá has 2 letters even though á takes 2 bytes
+        let easy_words = sorted(&[ "batata", "resolve", "papa", "chá", "carro", "baía", "macaco", "tia" ]); //baía has 3 syllables, chá and carro a digraph
+        let medium_words = sorted(&[ "impotente", "alarvará", "batata", "resolve", "carro", "palhinha", "macaco" ]);
+        let hard_words = sorted(&[ "hipotético", "pô-los" ,"impotente", "alarvará", "batata", "resolve", "palhinha", "macaco" ]);
+        let hardest_words = sorted(&[ "anticonstitucionalissimamente", "aâæãée-24ēçćc-bbò", "palhinha" ]); //palhinha is short but has lh and nh
 
         let mut all_words = [easiest_words.as_slice(), easy_words.as_slice(), medium_words.as_slice(), hard_words.as_slice(), hardest_words.as_slice()].concat();
         all_words.sort();
         all_words.dedup();
 
-        let easiest_allowed: Vec<String> = all_words.iter().filter(|w| allowed_difficulty(w,&Difficulty::Easiest)).map(|s| s.clone()).collect();
+        let easiest_allowed = allowed_at(&all_words, Difficulty::Easiest);
         assert_eq!(easiest_allowed, easiest_words, "Easiest difficulty is returning {:?} when it should be {:?}", easiest_allowed, easiest_words);
 
-        let easy_allowed: Vec<String> = all_words.iter().filter(|w| allowed_difficulty(w,&Difficulty::Easy)).map(|s| s.clone()).collect();
+        let easy_allowed = allowed_at(&all_words, Difficulty::Easy);
         assert_eq!(easy_allowed, easy_words, "Easy difficulty is returning {:?} when it should be {:?}", easy_allowed, easy_words);
 
-        let medium_allowed: Vec<String> = all_words.clone().into_iter().filter(|w| allowed_difficulty(w,&Difficulty::Medium)).collect();
+        let medium_allowed = allowed_at(&all_words, Difficulty::Medium);
         assert_eq!(medium_allowed, medium_words, "Medium difficulty is returning {:?} when it should be {:?}", medium_allowed, medium_words);
 
-        let hard_allowed: Vec<String> = all_words.clone().into_iter().filter(|w| allowed_difficulty(w,&Difficulty::Hard)).collect();
+        let hard_allowed = allowed_at(&all_words, Difficulty::Hard);
         assert_eq!(hard_allowed, hard_words, "Hard difficulty is returning {:?} when it should be {:?}", hard_allowed, hard_words);
 
-        let hardest_allowed: Vec<String> = all_words.into_iter().filter(|w| allowed_difficulty(w,&Difficulty::Hardest)).collect();
+        let hardest_allowed = allowed_at(&all_words, Difficulty::Hardest);
         assert_eq!(hardest_allowed, hardest_words, "Hardest difficulty is returning {:?} when it should be {:?}", hardest_allowed, hardest_words);
 
     }
+
+    #[test]
+    fn test_letter_count() {
+        assert_eq!(letter_count("pá"), 2);
+        assert_eq!(letter_count("pa\u{301}"), 2, "decomposed á is still one letter");
+        assert_eq!(letter_count("pô-los"), 6);
+    }
+
+    #[test]
+    fn test_hard_digraph_count() {
+        assert_eq!(PORTUGUESE.hard_digraph_count("pato"), 0);
+        assert_eq!(PORTUGUESE.hard_digraph_count("Chá"), 1);
+        assert_eq!(PORTUGUESE.hard_digraph_count("palhinha"), 2);
+        assert_eq!(PORTUGUESE.hard_digraph_count("quilha"), 2);
+    }
+
+    #[test]
+    fn test_difficulty_per_language() {
+        let words: Vec<String> = ["cat", "ship", "thick", "rabbit"].iter().map(|w| w.to_string()).collect();
+        assert_eq!(words.iter().filter(|w| allowed_difficulty(&ENGLISH, w, &Difficulty::Easiest)).collect::<Vec<_>>(), vec!["cat"], "ship and thick have digraphs, rabbit is too long");
+        assert!(allowed_difficulty(&ENGLISH, &"rabbit".to_string(), &Difficulty::Easy));
+        assert!(!allowed_difficulty(&PORTUGUESE, &"thick".to_string(), &Difficulty::Hardest));
+        assert!(allowed_difficulty(&ENGLISH, &"cake".to_string(), &Difficulty::Easiest), "English words are not split by the Portuguese rules (ca-ke)");
+        assert_eq!(PoolWord::new(&ENGLISH, "boat").syllables, Vec::<String>::new());
+        assert_eq!(PoolWord::new(&PORTUGUESE, "bola").syllables, vec!["bo", "la"]);
+    }
+
+    #[test]
+    fn test_allowed_sentence() {
+        let vowels_p_t = allowed_chars_regex(&"aAàÀáÁâÂãÃeEèÈéÉêÊiIíÍoOôÔòÒóÓõÕuUùÙúÚpPtT".to_owned()).unwrap();
+
+        assert!(allowed_sentence(&PORTUGUESE, "O pai e a tia.", &Difficulty::Easiest, Some(&vowels_p_t)));
+        assert!(!allowed_sentence(&PORTUGUESE, "O pato nada.", &Difficulty::Easiest, Some(&vowels_p_t)), "n and d are not allowed");
+        assert!(allowed_sentence(&PORTUGUESE, "O pato nada.", &Difficulty::Easiest, None));
+        assert!(!allowed_sentence(&PORTUGUESE, "O passarinho canta de manhã.", &Difficulty::Easy, None), "passarinho is too long for easy");
+        assert!(allowed_sentence(&PORTUGUESE, "O passarinho canta de manhã.", &Difficulty::Hardest, None));
+        assert!(!allowed_sentence(&PORTUGUESE, "O pai e a tia e o tio e a avó.", &Difficulty::Easiest, None), "too many words for easiest");
+        assert!(!allowed_sentence(&PORTUGUESE, "...", &Difficulty::Hardest, None));
+    }
 }
\ No newline at end of file
@@ -1,6 +1,30 @@
 pub mod app;
+pub mod backup;
+#[cfg(feature = "ssr")]
+pub mod config;
+pub mod curriculum;
+pub mod dictionary;
+pub mod i18n;
+pub mod languages;
 pub mod lexicanum;
+pub mod outcomes;
+pub mod pictures;
+pub mod profiles;
+pub mod progress;
+pub mod recognition;
+pub mod review;
+pub mod run_results;
+pub mod scores;
 pub mod setup_run;
+pub mod speech;
+pub mod spelling;
+pub mod stats;
+pub mod syllables;
+#[cfg(test)]
+mod test_support;
+pub mod word_index;
+#[cfg(feature = "ssr")]
+pub mod word_source;
 
 use cfg_if::cfg_if;
 
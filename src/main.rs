@@ -6,7 +6,62 @@ async fn main() -> std::io::Result<()> {
     use leptos::*;
     use leptos_actix::{generate_route_list, LeptosRoutes};
     use learn_to_read::app::*;
-    use learn_to_read::lexicanum;
+    use learn_to_read::config::{self, ServerConfig};
+    use learn_to_read::progress::ProgressStore;
+    use learn_to_read::word_index::WordIndex;
+    use std::collections::HashMap;
+
+    let server_config = match ServerConfig::from_env_and_args() {
+        Ok(server_config) => server_config,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, config::USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    // load the words once, before the workers start, and refuse to start with a wordlist that cannot be played
+    let data = match config::load_data(&server_config).await {
+        Ok(data) => data,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    for report in data.reports.iter() {
+        println!("{}", report);
+    }
+
+    let word_indexes: HashMap<String, String> = data.library.0
+        .iter()
+        .map(|(code, language)| {
+            let index = WordIndex::new(&language.words, &language.sentences, &language.lessons, &language.definitions, &language.pictures);
+            (code.clone(), serde_json::to_string(&index).expect("the word index is plain strings"))
+        })
+        .collect();
+    if let Some(dir) = &server_config.write_word_index {
+        let written = std::fs::create_dir_all(dir).and_then(|_| {
+            word_indexes.iter().try_for_each(|(code, json)| std::fs::write(format!("{}/{}.json", dir, code), json))
+        });
+        if let Err(e) = written {
+            eprintln!("couldnt write the word indexes to {}: {}", dir, e);
+            std::process::exit(1);
+        }
+        println!("word indexes written to {}", dir);
+        return Ok(());
+    }
+
+    let store = match ProgressStore::new(server_config.store_dir.as_ref().map(std::path::PathBuf::from)) {
+        Ok(store) => store,
+        Err(e) => {
+            eprintln!("couldnt open the progress store: {}", e);
+            std::process::exit(1);
+        }
+    };
+    println!("progress store: {}", store.describe());
+    let store_data = web::Data::new(store);
+    // the browser falls back to the word index when it cannot reach the server, so each is built once and served as is
+    let word_index_data = web::Data::new(WordIndexJson(word_indexes));
+    let library_data = web::Data::new(data.library);
 
     let conf = get_configuration(None).await.unwrap();
     let addr = conf.leptos_options.site_addr;
@@ -27,9 +82,14 @@ async fn main() -> std::io::Result<()> {
             .service(Files::new("/assets", site_root))
             // serve the favicon from /favicon.ico
             .service(favicon)
+            // the service worker has to be served from the root to look after every page
+            .service(service_worker)
+            .service(word_index_json)
             .leptos_routes(leptos_options.to_owned(), routes.to_owned(), App)
             .app_data(web::Data::new(leptos_options.to_owned()))
-            .app_data(web::Data::new(lexicanum::load_words("wordlist/wordlist-ao-latest.txt")).to_owned())
+            .app_data(library_data.clone())
+            .app_data(store_data.clone())
+            .app_data(word_index_data.clone())
         //.wrap(middleware::Compress::default())
     })
     .bind(&addr)?
@@ -47,6 +107,31 @@ async fn favicon( leptos_options: actix_web::web::Data<leptos::LeptosOptions> )
     ))?)
 }
 
+#[cfg(feature = "ssr")]
+#[actix_web::get("sw.js")]
+async fn service_worker( leptos_options: actix_web::web::Data<leptos::LeptosOptions> ) -> actix_web::Result<actix_files::NamedFile> {
+    let leptos_options = leptos_options.into_inner();
+    let site_root = &leptos_options.site_root;
+    Ok(actix_files::NamedFile::open(format!(
+        "{site_root}/sw.js"
+    ))?)
+}
+
+/// The word index of each language, already serialized, by language code.
+#[cfg(feature = "ssr")]
+struct WordIndexJson(std::collections::HashMap<String, String>);
+
+#[cfg(feature = "ssr")]
+#[actix_web::get("word-index/{code}.json")]
+async fn word_index_json( index: actix_web::web::Data<WordIndexJson>, code: actix_web::web::Path<String> ) -> actix_web::HttpResponse {
+    match index.0.get(code.as_str()) {
+        Some(json) => actix_web::HttpResponse::Ok()
+            .content_type("application/json")
+            .body(json.clone()),
+        None => actix_web::HttpResponse::NotFound().finish(),
+    }
+}
+
 #[cfg(not(any(feature = "ssr", feature = "csr")))]
 pub fn main() {
     // no client-side main function
@@ -0,0 +1,70 @@
+use serde::{Serialize, Deserialize};
+
+use crate::app::{Difficulty, ReadingMode};
+
+/// How many word outcomes a profile keeps, oldest dropped first, so local storage does not grow forever.
+pub const MAX_OUTCOMES: usize = 5000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Outcome {
+    /// Read without help.
+    Read,
+    NeededHelp,
+}
+
+/// How reading a single word (or sentence) went.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WordOutcome {
+    pub text: String,
+    pub reading_mode: ReadingMode,
+    pub difficulty: Difficulty,
+    pub outcome: Outcome,
+    /// Time between the word showing up and one of the outcome buttons being pressed.
+    pub duration_ms: u32,
+    /// When the outcome was recorded, as a js timestamp.
+    pub at: f64,
+    /// Code of the language read, empty for outcomes recorded before languages existed (the default one).
+    #[serde(default)]
+    pub language: String,
+}
+
+/// Adds an outcome to a profile's history, dropping the oldest ones past [`MAX_OUTCOMES`].
+pub fn record(history: &mut Vec<WordOutcome>, outcome: WordOutcome) {
+    history.push(outcome);
+    if history.len() > MAX_OUTCOMES {
+        history.drain(..history.len() - MAX_OUTCOMES);
+    }
+}
+
+/// Words that needed help, without repeats, in the order they were read.
+pub fn needed_help(outcomes: &[WordOutcome]) -> Vec<String> {
+    let mut words: Vec<String> = vec![];
+    for o in outcomes.iter().filter(|o| o.outcome == Outcome::NeededHelp) {
+        if !words.contains(&o.text) {
+            words.push(o.text.clone());
+        }
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::outcome;
+
+    #[test]
+    fn test_record() {
+        let mut history: Vec<WordOutcome> = (0..MAX_OUTCOMES).map(|i| outcome("pato", Outcome::Read, i as f64)).collect();
+        record(&mut history, outcome("bola", Outcome::NeededHelp, MAX_OUTCOMES as f64));
+
+        assert_eq!(history.len(), MAX_OUTCOMES);
+        assert_eq!(history[0].at, 1.0, "the oldest outcome is dropped");
+        assert_eq!(history.last().map(|o| o.text.as_str()), Some("bola"));
+    }
+
+    #[test]
+    fn test_needed_help() {
+        let outcomes = [ outcome("pato", Outcome::NeededHelp, 1.0), outcome("bola", Outcome::Read, 2.0), outcome("chá", Outcome::NeededHelp, 3.0), outcome("pato", Outcome::NeededHelp, 4.0) ];
+        assert_eq!(needed_help(&outcomes), vec!["pato", "chá"]);
+    }
+}
@@ -0,0 +1,67 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Where the asset files are served from. Picture manifests give paths relative to the `assets` directory.
+const ASSETS_URL: &str = "/assets";
+
+#[derive(Debug, PartialEq)]
+pub enum PicturesError {
+    /// The line is not written as `word | path`.
+    Malformed { line: usize },
+    /// The path leaves the `assets` directory, so the picture would not be served.
+    OutsideAssets { line: usize },
+}
+
+impl fmt::Display for PicturesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PicturesError::Malformed { line } => write!(f, "picture {} is not `word | path`", line),
+            PicturesError::OutsideAssets { line } => write!(f, "picture {} is not under assets/", line),
+        }
+    }
+}
+
+impl std::error::Error for PicturesError {}
+
+/// Parses a picture manifest, one word per line: `word | path`, the path relative to `assets/`,
+/// e.g. `pato | pictures/pato.svg`. Blank lines and lines starting with `#` are skipped, but still counted in error line numbers.
+/// Words are looked up in lowercase.
+pub fn parse_pictures(lines: &[String]) -> Result<BTreeMap<String, String>, PicturesError> {
+    let mut pictures = BTreeMap::new();
+    for (i, l) in lines.iter().enumerate().filter(|(_, l)| !l.trim().is_empty() && !l.trim().starts_with('#')) {
+        let line = i + 1;
+        match l.split_once('|').map(|(w, p)| (w.trim(), p.trim())) {
+            Some((_, path)) if path.starts_with('/') || path.split('/').any(|part| part == "..") => {
+                return Err(PicturesError::OutsideAssets { line });
+            },
+            Some((word, path)) if !word.is_empty() && !path.is_empty() => {
+                pictures.insert(word.to_lowercase(), path.to_string());
+            },
+            _ => return Err(PicturesError::Malformed { line }),
+        }
+    }
+    Ok(pictures)
+}
+
+/// Where the browser finds the picture at `path` (relative to `assets/`).
+pub fn picture_url(path: &str) -> String {
+    format!("{}/{}", ASSETS_URL, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::lines;
+
+    #[test]
+    fn test_parse_pictures() {
+        let pictures = parse_pictures(&lines(&["Pato | pictures/pato.svg", "sol|pictures/sol.svg"])).unwrap();
+        assert_eq!(pictures.get("pato").map(|p| picture_url(p)), Some("/assets/pictures/pato.svg".to_string()));
+        assert_eq!(pictures.len(), 2);
+
+        assert_eq!(parse_pictures(&lines(&["pato | pictures/pato.svg", "sol"])), Err(PicturesError::Malformed { line: 2 }));
+        assert_eq!(parse_pictures(&lines(&["pato | ../src/main.rs"])), Err(PicturesError::OutsideAssets { line: 1 }));
+        assert_eq!(parse_pictures(&lines(&["pato | /etc/passwd"])), Err(PicturesError::OutsideAssets { line: 1 }));
+        assert_eq!(parse_pictures(&lines(&["# palavra | imagem", "", "sol | ../sol.svg"])), Err(PicturesError::OutsideAssets { line: 3 }), "lines are numbered as in the file");
+    }
+}
@@ -0,0 +1,333 @@
+use leptos::*;
+use leptos_router::*;
+use leptos_use::storage::{use_local_storage, JsonCodec};
+use serde::{Serialize, Deserialize};
+use std::fmt;
+
+use crate::i18n::{self, Locale, Msg};
+use crate::progress;
+
+/// A child reading on this device. Every setting and run history is stored under the profile's own keys.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Profile {
+    /// Random, so that profiles created on different devices do not end up sharing an id when synced.
+    pub id: u64,
+    pub name: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProfileError {
+    EmptyName,
+    DuplicateName,
+    UnknownProfile,
+}
+
+impl ProfileError {
+    /// Text shown to the parent in the profile picker, in the app's locale.
+    pub fn message(&self, locale: Locale) -> &'static str {
+        let msg = match self {
+            ProfileError::EmptyName => Msg::EmptyName,
+            ProfileError::DuplicateName => Msg::DuplicateName,
+            ProfileError::UnknownProfile => Msg::UnknownProfile,
+        };
+        msg.text(locale)
+    }
+}
+
+impl fmt::Display for ProfileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message(Locale::default()))
+    }
+}
+
+/// The local storage key of a setting or history belonging to a profile.
+pub fn storage_key(profile_id: u64, key: &str) -> String {
+    format!("profile_{}_{}", profile_id, key)
+}
+
+/// Keys the settings and run history were stored under before there were profiles, besides the `tk_<label>` keyboard keys.
+const LEGACY_KEYS: [&str; 8] = ["allowed_chars", "all_words", "difficulty", "reading_mode", "game_mode", "run_history", "show_syllables", "lesson"];
+
+fn is_legacy_key(key: &str) -> bool {
+    LEGACY_KEYS.contains(&key) || key.starts_with("tk_")
+}
+
+fn checked_name(profiles: &[Profile], name: &str, except_id: Option<u64>) -> Result<String, ProfileError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(ProfileError::EmptyName);
+    }
+    match profiles.iter().any(|p| Some(p.id) != except_id && p.name.to_lowercase() == name.to_lowercase()) {
+        true => Err(ProfileError::DuplicateName),
+        false => Ok(name.to_string()),
+    }
+}
+
+/// The largest integer javascript numbers hold exactly, so ids survive any JSON tooling.
+const MAX_PROFILE_ID: u64 = (1 << 53) - 1;
+
+/// A new random profile id, between 1 and [`MAX_PROFILE_ID`].
+fn random_profile_id() -> u64 {
+    (js_sys::Math::random() * MAX_PROFILE_ID as f64) as u64 + 1
+}
+
+/// Adds a profile with an id drawn from `new_id`, drawing again in the unlikely case it is taken.
+pub fn create_profile(profiles: &mut Vec<Profile>, name: &str, mut new_id: impl FnMut() -> u64) -> Result<Profile, ProfileError> {
+    let name = checked_name(profiles, name, None)?;
+    let mut id = new_id();
+    while profiles.iter().any(|p| p.id == id) {
+        id = new_id();
+    }
+    let profile = Profile { id, name };
+    profiles.push(profile.clone());
+    Ok(profile)
+}
+
+pub fn rename_profile(profiles: &mut [Profile], id: u64, name: &str) -> Result<(), ProfileError> {
+    let name = checked_name(profiles, name, Some(id))?;
+    let profile = profiles.iter_mut().find(|p| p.id == id).ok_or(ProfileError::UnknownProfile)?;
+    profile.name = name;
+    Ok(())
+}
+
+pub fn delete_profile(profiles: &mut Vec<Profile>, id: u64) -> Result<Profile, ProfileError> {
+    let index = profiles.iter().position(|p| p.id == id).ok_or(ProfileError::UnknownProfile)?;
+    Ok(profiles.remove(index))
+}
+
+/// Drops everything stored under a deleted profile's keys.
+fn clear_profile_storage(profile_id: u64) {
+    let prefix = storage_key(profile_id, "");
+    let Ok(Some(storage)) = window().local_storage() else {
+        return;
+    };
+    let keys: Vec<String> = (0..storage.length().unwrap_or(0))
+        .filter_map(|i| storage.key(i).ok().flatten())
+        .filter(|k| k.starts_with(&prefix))
+        .collect();
+    for k in keys {
+        let _ = storage.remove_item(&k);
+    }
+}
+
+/// Moves what was stored before there were profiles under the first profile created, so the child reading
+/// until then keeps their settings and runs.
+fn migrate_legacy_storage(profile_id: u64) {
+    let Ok(Some(storage)) = window().local_storage() else {
+        return;
+    };
+    let keys: Vec<String> = (0..storage.length().unwrap_or(0))
+        .filter_map(|i| storage.key(i).ok().flatten())
+        .filter(|k| is_legacy_key(k))
+        .collect();
+    for k in keys {
+        if let Ok(Some(value)) = storage.get_item(&k) {
+            let _ = storage.set_item(&storage_key(profile_id, &k), &value);
+        }
+        let _ = storage.remove_item(&k);
+    }
+}
+
+fn sync_profile(profile: Profile) {
+    spawn_local(async move {
+        if let Err(e) = progress::store_profile(profile).await {
+            progress::log_sync_error("profile", &e);
+        }
+    });
+}
+
+fn remove_on_server(id: u64) {
+    spawn_local(async move {
+        if let Err(e) = progress::remove_stored_profile(id).await {
+            progress::log_sync_error("profile", &e);
+        }
+    });
+}
+
+/// The profiles on this device and the one currently reading, as kept in local storage.
+#[derive(Clone, Copy)]
+pub struct StoredProfiles {
+    pub profiles: Signal<Vec<Profile>>,
+    pub set_profiles: WriteSignal<Vec<Profile>>,
+    pub current_profile: Signal<Option<u64>>,
+    pub set_current_profile: WriteSignal<Option<u64>>,
+}
+
+pub fn use_profiles() -> StoredProfiles {
+    let (profiles, set_profiles, _) = use_local_storage::<Vec<Profile>, JsonCodec>("profiles");
+    let (current_profile, set_current_profile, _) = use_local_storage::<Option<u64>, JsonCodec>("current_profile");
+    StoredProfiles { profiles, set_profiles, current_profile, set_current_profile }
+}
+
+/// Picks who is going to read, and lets parents add, rename and remove readers.
+#[component]
+pub fn profile_picker() -> impl IntoView {
+    let StoredProfiles { profiles, set_profiles, current_profile, set_current_profile } = use_profiles();
+    let (new_name, set_new_name) = create_signal("".to_string());
+    let editing = create_rw_signal(None::<u64>);
+    let edited_name = create_rw_signal("".to_string());
+    let confirm_delete = create_rw_signal(None::<u64>);
+    let error = create_rw_signal(None::<ProfileError>);
+    let locale = i18n::use_locale().locale;
+
+    // bring in the profiles created on other devices, and hand ours to the server. Removals travel both ways:
+    // the ones made here that did not reach the server yet are sent again, and the ones made elsewhere are dropped here
+    let (removed_profiles, set_removed_profiles, _) = use_local_storage::<Vec<u64>, JsonCodec>("removed_profiles");
+    let stored_profiles = create_local_resource(|| (), |_| async move { progress::load_stored_profiles().await });
+    create_effect(move |_| match stored_profiles.get() {
+        Some(Ok(stored)) => {
+            let removed_here: Vec<u64> = removed_profiles.get_untracked().into_iter().filter(|id| !stored.removed.contains(id)).collect();
+            for id in removed_here.iter().copied() {
+                remove_on_server(id);
+            }
+            let removed: Vec<u64> = removed_here.iter().chain(&stored.removed).copied().collect();
+            let local_only: Vec<Profile> = profiles.with_untracked(|local| local.iter().filter(|p| !stored.profiles.contains(p) && !removed.contains(&p.id)).cloned().collect());
+            let mut merged = profiles.get_untracked();
+            progress::merge_profiles(&mut merged, stored.profiles, &removed);
+            for id in profiles.with_untracked(|local| local.iter().map(|p| p.id).filter(|id| stored.removed.contains(id)).collect::<Vec<_>>()) {
+                clear_profile_storage(id);
+                if current_profile.get_untracked() == Some(id) {
+                    set_current_profile.set(None);
+                }
+            }
+            set_profiles.set(merged);
+            set_removed_profiles.set(removed_here);
+            for profile in local_only {
+                sync_profile(profile);
+            }
+        },
+        Some(Err(e)) => progress::log_sync_error("profiles", &e),
+        None => {},
+    });
+
+    let navigate = store_value(use_navigate());
+    let pick = move |id: u64| {
+        set_current_profile.set(Some(id));
+        navigate.with_value(|navigate| navigate("/", Default::default()));
+    };
+
+    let create = move |_| {
+        let mut updated = profiles.get_untracked();
+        let first = updated.is_empty();
+        match create_profile(&mut updated, &new_name.get_untracked(), random_profile_id) {
+            Ok(profile) => {
+                if first {
+                    migrate_legacy_storage(profile.id);
+                }
+                set_profiles.set(updated);
+                sync_profile(profile);
+                set_new_name.set("".to_string());
+                error.set(None);
+            },
+            Err(e) => error.set(Some(e)),
+        }
+    };
+
+    let rename = move |id: u64| {
+        let mut updated = profiles.get_untracked();
+        match rename_profile(&mut updated, id, &edited_name.get_untracked()) {
+            Ok(_) => {
+                if let Some(profile) = updated.iter().find(|p| p.id == id) {
+                    sync_profile(profile.clone());
+                }
+                set_profiles.set(updated);
+                editing.set(None);
+                error.set(None);
+            },
+            Err(e) => error.set(Some(e)),
+        }
+    };
+
+    let delete = move |id: u64| {
+        let mut updated = profiles.get_untracked();
+        if delete_profile(&mut updated, id).is_ok() {
+            clear_profile_storage(id);
+            set_removed_profiles.update(|removed| removed.push(id));
+            remove_on_server(id);
+            set_profiles.set(updated);
+            if current_profile.get_untracked() == Some(id) {
+                set_current_profile.set(None);
+            }
+        }
+        confirm_delete.set(None);
+    };
+
+    let profile_row = move |profile: Profile| {
+        let id = profile.id;
+        let name = profile.name.clone();
+        view! {
+            <div class="profile">
+                {move || match editing.get() == Some(id) {
+                    true => view! {
+                        <input type="text" class="profile-name-input" prop:value=edited_name on:input=move |ev| edited_name.set(event_target_value(&ev)) />
+                        <div class="key" on:click=move |_| rename(id)>{move || Msg::Save.text(locale.get())}</div>
+                        <div class="key" on:click=move |_| editing.set(None)>{move || Msg::Cancel.text(locale.get())}</div>
+                    }.into_view(),
+                    false => {
+                        let name = name.clone();
+                        view! {
+                            <div class="key profile-name" class:active=move || current_profile.get() == Some(id) on:click=move |_| pick(id)>{name.clone()}</div>
+                            <div class="key" on:click={
+                                let name = name.clone();
+                                move |_| { edited_name.set(name.clone()); editing.set(Some(id)); }
+                            }>{move || Msg::Rename.text(locale.get())}</div>
+                            {move || match confirm_delete.get() == Some(id) {
+                                true => view! { <div class="key delete-profile" on:click=move |_| delete(id)>{move || Msg::ReallyDelete.text(locale.get())}</div> }.into_view(),
+                                false => view! { <div class="key" on:click=move |_| confirm_delete.set(Some(id))>{move || Msg::Delete.text(locale.get())}</div> }.into_view(),
+                            }}
+                        }.into_view()
+                    },
+                }}
+            </div>
+        }
+    };
+
+    view! {
+        <h1 class="settings-title">{move || Msg::WhoIsReading.text(locale.get())}</h1>
+        <div class="flex-center">
+            <div class="profiles">
+                <For
+                    each=move || profiles.get()
+                    key=|profile| (profile.id, profile.name.clone())
+                    children=profile_row
+                />
+            </div>
+        </div>
+        <div class="flex-center">
+            <div class="new-profile">
+                <input type="text" class="profile-name-input" placeholder=move || Msg::Name.text(locale.get()) prop:value=new_name on:input=move |ev| set_new_name.set(event_target_value(&ev)) />
+                <div class="key" on:click=create>{move || Msg::NewReader.text(locale.get())}</div>
+            </div>
+        </div>
+        <div class="setup-error" style:display=move || if error.get().is_some() { "block" } else { "none" }>{move || error.get().map(|e| e.message(locale.get()))}</div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profiles() {
+        let mut profiles = vec![];
+
+        let ana = create_profile(&mut profiles, " Ana ", || 1).unwrap();
+        assert_eq!(ana, Profile { id: 1, name: "Ana".to_string() });
+        let mut ids = [1, 1, 2].into_iter();
+        let rui = create_profile(&mut profiles, "Rui", || ids.next().unwrap()).unwrap();
+        assert_eq!(rui.id, 2, "a taken id is drawn again");
+        assert_eq!(create_profile(&mut profiles, "ana", || 3), Err(ProfileError::DuplicateName));
+        assert_eq!(create_profile(&mut profiles, "  ", || 3), Err(ProfileError::EmptyName));
+
+        assert_eq!(rename_profile(&mut profiles, 1, "Rui"), Err(ProfileError::DuplicateName));
+        assert_eq!(rename_profile(&mut profiles, 1, "ANA"), Ok(()), "a profile can change the case of its own name");
+        assert_eq!(rename_profile(&mut profiles, 7, "Zé"), Err(ProfileError::UnknownProfile));
+
+        assert_eq!(delete_profile(&mut profiles, 1).map(|p| p.name), Ok("ANA".to_string()));
+        assert_eq!(delete_profile(&mut profiles, 1), Err(ProfileError::UnknownProfile));
+        assert_eq!(storage_key(3, "difficulty"), "profile_3_difficulty");
+
+        assert!(is_legacy_key("run_history") && is_legacy_key("tk_nh"));
+        assert!(!is_legacy_key("profiles") && !is_legacy_key("current_profile") && !is_legacy_key("profile_3_tk_nh"));
+    }
+}
@@ -0,0 +1,394 @@
+use leptos::{server, ServerFnError};
+use serde::{Serialize, Deserialize};
+use std::fmt;
+use std::str::FromStr;
+use cfg_if::cfg_if;
+
+use crate::app::{Difficulty, GameMode, ReadingMode, DEFAULT_TIME_LIMIT_SECS};
+use crate::curriculum::Lesson;
+use crate::dictionary::DictionaryProvider;
+use crate::profiles::Profile;
+use crate::scores::RunRecord;
+use crate::word_index;
+
+/// A profile's setup screen settings, as kept in the server side store.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProfileSettings {
+    /// Letters switched on in the setup keyboard, by their key label.
+    pub letters: Vec<String>,
+    pub all_words: bool,
+    pub difficulty: Difficulty,
+    pub reading_mode: ReadingMode,
+    pub game_mode: GameMode,
+    /// How long a time attack lasts. Settings saved before it was kept get the default limit.
+    #[serde(default = "default_time_limit_secs")]
+    pub time_limit_secs: u32,
+    pub show_syllables: bool,
+    pub lesson: Option<Lesson>,
+    /// Code of the language being read, empty for the default one (settings saved before languages existed).
+    #[serde(default)]
+    pub language: String,
+    /// The listen button only shows up once the child says they tried reading the word alone.
+    #[serde(default)]
+    pub listen_after_trying: bool,
+    #[serde(default)]
+    pub dictionary: DictionaryProvider,
+    #[serde(default)]
+    pub with_pictures: bool,
+    /// The picture stays covered until the child has read the word.
+    #[serde(default)]
+    pub picture_after_reading: bool,
+    /// When the settings were last changed, as a js timestamp. The newest copy wins when syncing.
+    pub updated_at: f64,
+}
+
+fn default_time_limit_secs() -> u32 {
+    DEFAULT_TIME_LIMIT_SECS
+}
+
+/// Why the server side store could not be used. Like [`crate::lexicanum::WordPoolError`] it travels
+/// as a `ServerFnError::ServerError` holding the error code.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StoreError {
+    /// The server was started without `--store`, progress is only kept in the browser.
+    Disabled,
+    /// Reading or writing the store files failed.
+    Io,
+}
+
+const STORE_ERROR_PREFIX: &str = "store_error:";
+
+impl StoreError {
+    pub fn from_server_fn_error(error: &ServerFnError) -> Option<StoreError> {
+        match error {
+            ServerFnError::ServerError(code) => code.parse().ok(),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let code = match self {
+            StoreError::Disabled => "disabled",
+            StoreError::Io => "io",
+        };
+        write!(f, "{}{}", STORE_ERROR_PREFIX, code)
+    }
+}
+
+impl FromStr for StoreError {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix(STORE_ERROR_PREFIX) {
+            Some("disabled") => Ok(StoreError::Disabled),
+            Some("io") => Ok(StoreError::Io),
+            _ => Err(()),
+        }
+    }
+}
+
+impl From<StoreError> for ServerFnError {
+    fn from(error: StoreError) -> Self {
+        ServerFnError::ServerError(error.to_string())
+    }
+}
+
+/// The profiles kept on the server, and the ids of the ones removed from it so other devices drop them as well.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct StoredProfileList {
+    pub profiles: Vec<Profile>,
+    pub removed: Vec<u64>,
+}
+
+/// Adds the profiles only known to `other` to `profiles`, and drops the `removed` ones.
+/// Profiles known to both keep their local name.
+pub fn merge_profiles(profiles: &mut Vec<Profile>, other: Vec<Profile>, removed: &[u64]) {
+    for profile in other {
+        if !profiles.iter().any(|p| p.id == profile.id) {
+            profiles.push(profile);
+        }
+    }
+    profiles.retain(|p| !removed.contains(&p.id));
+}
+
+/// Adds the runs only known to `other` to `history`, keeping it in the order the runs finished.
+pub fn merge_runs(history: &mut Vec<RunRecord>, other: Vec<RunRecord>) {
+    for run in other {
+        if !history.contains(&run) {
+            history.push(run);
+        }
+    }
+    history.sort_by(|a, b| a.finished_at.total_cmp(&b.finished_at));
+}
+
+/// Logs a failed sync, staying quiet when the server simply has no store, or when there is no server at all.
+pub fn log_sync_error(what: &str, error: &ServerFnError) {
+    if !word_index::SERVERLESS && StoreError::from_server_fn_error(error) != Some(StoreError::Disabled) {
+        leptos::logging::log!("could not sync {}: {}", what, error);
+    }
+}
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use actix_web::web::Data;
+        use leptos_actix::extract;
+        use serde::de::DeserializeOwned;
+        use std::path::{Path, PathBuf};
+        use std::sync::Mutex;
+
+        /// Everything stored about one profile.
+        #[derive(Default, Serialize, Deserialize)]
+        struct ProfileRecord {
+            settings: Option<ProfileSettings>,
+            runs: Vec<RunRecord>,
+        }
+
+        /// Progress kept on the server as JSON files in a directory: `profiles.json`, `removed-profiles.json`
+        /// and one `profile-<id>.json` per profile.
+        /// Writes go through a temporary file so a crash never leaves half a file behind.
+        pub struct ProgressStore {
+            dir: Option<PathBuf>,
+            lock: Mutex<()>,
+        }
+
+        impl ProgressStore {
+            pub fn new(dir: Option<PathBuf>) -> Result<Self, std::io::Error> {
+                if let Some(dir) = &dir {
+                    std::fs::create_dir_all(dir)?;
+                }
+                Ok(ProgressStore { dir, lock: Mutex::new(()) })
+            }
+
+            pub fn describe(&self) -> String {
+                match &self.dir {
+                    Some(dir) => format!("directory {}", dir.display()),
+                    None => "disabled".to_string(),
+                }
+            }
+
+            fn dir(&self) -> Result<&Path, StoreError> {
+                self.dir.as_deref().ok_or(StoreError::Disabled)
+            }
+
+            fn read<T: DeserializeOwned + Default>(&self, file: &str) -> Result<T, StoreError> {
+                let path = self.dir()?.join(file);
+                match std::fs::read_to_string(&path) {
+                    Ok(json) => serde_json::from_str(&json).map_err(|e| {
+                        leptos::logging::log!("{} is not valid: {}", path.display(), e);
+                        StoreError::Io
+                    }),
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(T::default()),
+                    Err(e) => {
+                        leptos::logging::log!("couldnt read {}: {}", path.display(), e);
+                        Err(StoreError::Io)
+                    },
+                }
+            }
+
+            fn write<T: Serialize>(&self, file: &str, value: &T) -> Result<(), StoreError> {
+                let path = self.dir()?.join(file);
+                let tmp = path.with_extension("json.tmp");
+                let json = serde_json::to_string_pretty(value).map_err(|_| StoreError::Io)?;
+                std::fs::write(&tmp, json).and_then(|_| std::fs::rename(&tmp, &path)).map_err(|e| {
+                    leptos::logging::log!("couldnt write {}: {}", path.display(), e);
+                    StoreError::Io
+                })
+            }
+
+            fn update<T: Serialize + DeserializeOwned + Default>(&self, file: &str, f: impl FnOnce(&mut T)) -> Result<(), StoreError> {
+                let _guard = self.lock.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+                let mut value: T = self.read(file)?;
+                f(&mut value);
+                self.write(file, &value)
+            }
+
+            fn profile_file(id: u64) -> String {
+                format!("profile-{}.json", id)
+            }
+
+            pub fn profiles(&self) -> Result<Vec<Profile>, StoreError> {
+                self.read("profiles.json")
+            }
+
+            /// Ids of the removed profiles, kept so a device that still has one of them does not bring it back.
+            pub fn removed_profiles(&self) -> Result<Vec<u64>, StoreError> {
+                self.read("removed-profiles.json")
+            }
+
+            pub fn save_profile(&self, profile: Profile) -> Result<(), StoreError> {
+                if self.removed_profiles()?.contains(&profile.id) {
+                    return Ok(());
+                }
+                self.update("profiles.json", |profiles: &mut Vec<Profile>| {
+                    match profiles.iter_mut().find(|p| p.id == profile.id) {
+                        Some(p) => p.name = profile.name,
+                        None => profiles.push(profile),
+                    }
+                })
+            }
+
+            pub fn remove_profile(&self, id: u64) -> Result<(), StoreError> {
+                self.update("removed-profiles.json", |removed: &mut Vec<u64>| if !removed.contains(&id) { removed.push(id) })?;
+                self.update("profiles.json", |profiles: &mut Vec<Profile>| profiles.retain(|p| p.id != id))?;
+                match std::fs::remove_file(self.dir()?.join(Self::profile_file(id))) {
+                    Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(StoreError::Io),
+                    _ => Ok(()),
+                }
+            }
+
+            pub fn settings(&self, id: u64) -> Result<Option<ProfileSettings>, StoreError> {
+                Ok(self.read::<ProfileRecord>(&Self::profile_file(id))?.settings)
+            }
+
+            /// Keeps whichever settings were changed last.
+            pub fn save_settings(&self, id: u64, settings: ProfileSettings) -> Result<(), StoreError> {
+                self.update(&Self::profile_file(id), |record: &mut ProfileRecord| {
+                    if record.settings.as_ref().is_none_or(|s| s.updated_at <= settings.updated_at) {
+                        record.settings = Some(settings);
+                    }
+                })
+            }
+
+            pub fn runs(&self, id: u64) -> Result<Vec<RunRecord>, StoreError> {
+                Ok(self.read::<ProfileRecord>(&Self::profile_file(id))?.runs)
+            }
+
+            pub fn save_runs(&self, id: u64, runs: Vec<RunRecord>) -> Result<(), StoreError> {
+                self.update(&Self::profile_file(id), |record: &mut ProfileRecord| merge_runs(&mut record.runs, runs))
+            }
+
+            /// Drops every run kept for the profile in favour of `runs`.
+            pub fn replace_runs(&self, id: u64, runs: Vec<RunRecord>) -> Result<(), StoreError> {
+                self.update(&Self::profile_file(id), |record: &mut ProfileRecord| record.runs = runs)
+            }
+        }
+
+        async fn progress_store() -> Result<Data<ProgressStore>, StoreError> {
+            extract(|store: Data<ProgressStore>| async move { store })
+                .await
+                .map_err(|_| StoreError::Disabled)
+        }
+    }
+}
+
+#[server]
+pub async fn load_stored_profiles() -> Result<StoredProfileList, ServerFnError> {
+    let store = progress_store().await?;
+    Ok(StoredProfileList { profiles: store.profiles()?, removed: store.removed_profiles()? })
+}
+
+#[server(StoreProfile, "/api", "Cbor")]
+pub async fn store_profile(profile: Profile) -> Result<(), ServerFnError> {
+    Ok(progress_store().await?.save_profile(profile)?)
+}
+
+#[server]
+pub async fn remove_stored_profile(id: u64) -> Result<(), ServerFnError> {
+    Ok(progress_store().await?.remove_profile(id)?)
+}
+
+#[server]
+pub async fn load_stored_settings(id: u64) -> Result<Option<ProfileSettings>, ServerFnError> {
+    Ok(progress_store().await?.settings(id)?)
+}
+
+#[server(StoreSettings, "/api", "Cbor")]
+pub async fn store_settings(id: u64, settings: ProfileSettings) -> Result<(), ServerFnError> {
+    Ok(progress_store().await?.save_settings(id, settings)?)
+}
+
+#[server]
+pub async fn load_stored_runs(id: u64) -> Result<Vec<RunRecord>, ServerFnError> {
+    Ok(progress_store().await?.runs(id)?)
+}
+
+/// Saves runs finished on this device. Runs the server already has are ignored, so the whole local history can be sent.
+#[server(StoreRuns, "/api", "Cbor")]
+pub async fn store_runs(id: u64, runs: Vec<RunRecord>) -> Result<(), ServerFnError> {
+    Ok(progress_store().await?.save_runs(id, runs)?)
+}
+
+/// Replaces the profile's whole run history, for when a backup is restored over it.
+#[server(ReplaceStoredRuns, "/api", "Cbor")]
+pub async fn replace_stored_runs(id: u64, runs: Vec<RunRecord>) -> Result<(), ServerFnError> {
+    Ok(progress_store().await?.replace_runs(id, runs)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::run_at;
+
+    #[test]
+    fn test_merge() {
+        let mut history = vec![run_at(1.0), run_at(3.0)];
+        merge_runs(&mut history, vec![run_at(3.0), run_at(2.0)]);
+        assert_eq!(history, vec![run_at(1.0), run_at(2.0), run_at(3.0)]);
+
+        let mut profiles = vec![Profile { id: 2, name: "Ana".to_string() }];
+        merge_profiles(&mut profiles, vec![Profile { id: 1, name: "Rui".to_string() }, Profile { id: 2, name: "Ana Maria".to_string() }], &[]);
+        assert_eq!(profiles, vec![Profile { id: 2, name: "Ana".to_string() }, Profile { id: 1, name: "Rui".to_string() }]);
+        merge_profiles(&mut profiles, vec![Profile { id: 1, name: "Rui".to_string() }, Profile { id: 3, name: "Zé".to_string() }], &[1, 3]);
+        assert_eq!(profiles, vec![Profile { id: 2, name: "Ana".to_string() }], "removed profiles are dropped, wherever they come from");
+    }
+
+    #[cfg(feature = "ssr")]
+    #[test]
+    fn test_progress_store() {
+        // one directory per test process, so test runs at the same time do not share a store
+        let dir = std::env::temp_dir().join(format!("learn-to-read-test-store-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let store = ProgressStore::new(Some(dir.clone())).unwrap();
+
+        assert_eq!(store.profiles(), Ok(vec![]));
+        store.save_profile(Profile { id: 1, name: "Ana".to_string() }).unwrap();
+        store.save_profile(Profile { id: 1, name: "Ana Maria".to_string() }).unwrap();
+        assert_eq!(store.profiles(), Ok(vec![Profile { id: 1, name: "Ana Maria".to_string() }]));
+
+        let settings = |updated_at: f64| ProfileSettings {
+            letters: vec!["a".to_string(), "p".to_string()],
+            all_words: false,
+            difficulty: Difficulty::Easiest,
+            reading_mode: ReadingMode::Words,
+            game_mode: GameMode::Classic,
+            time_limit_secs: 30,
+            show_syllables: true,
+            lesson: None,
+            language: String::new(),
+            listen_after_trying: false,
+            dictionary: DictionaryProvider::Local,
+            with_pictures: true,
+            picture_after_reading: false,
+            updated_at,
+        };
+        store.save_settings(1, settings(2.0)).unwrap();
+        store.save_settings(1, settings(1.0)).unwrap();
+        assert_eq!(store.settings(1), Ok(Some(settings(2.0))), "older settings do not overwrite newer ones");
+
+        store.save_runs(1, vec![run_at(1.0)]).unwrap();
+        store.save_runs(1, vec![run_at(1.0), run_at(2.0)]).unwrap();
+        assert_eq!(store.runs(1), Ok(vec![run_at(1.0), run_at(2.0)]));
+        store.replace_runs(1, vec![run_at(3.0)]).unwrap();
+        assert_eq!(store.runs(1), Ok(vec![run_at(3.0)]));
+
+        store.remove_profile(1).unwrap();
+        assert_eq!(store.profiles(), Ok(vec![]));
+        assert_eq!(store.runs(1), Ok(vec![]));
+        assert_eq!(store.removed_profiles(), Ok(vec![1]));
+        store.save_profile(Profile { id: 1, name: "Ana Maria".to_string() }).unwrap();
+        assert_eq!(store.profiles(), Ok(vec![]), "a device that missed the removal does not bring the profile back");
+
+        assert_eq!(ProgressStore::new(None).unwrap().profiles(), Err(StoreError::Disabled));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_store_error_round_trip() {
+        for error in [StoreError::Disabled, StoreError::Io] {
+            assert_eq!(StoreError::from_server_fn_error(&error.clone().into()), Some(error));
+        }
+        assert_eq!(StoreError::from_server_fn_error(&ServerFnError::ServerError("word_pool_error:empty_filter".to_owned())), None);
+    }
+}
@@ -0,0 +1,49 @@
+use leptos::*;
+
+use crate::i18n::{self, Msg};
+use crate::outcomes::Outcome;
+
+/// The choices of a recognition question, as buttons. A wrong pick is marked and cannot be picked again;
+/// once the right word is picked, a button hands the outcome to `ondone`: read alone when it was the first pick.
+#[component]
+pub fn choice_board(word: String, choices: Vec<String>, #[prop(into)] ondone: Callback<Outcome>) -> impl IntoView {
+    let locale = i18n::use_locale().locale;
+    let picked = create_rw_signal(Vec::<String>::new());
+    let answered = {
+        let word = word.clone();
+        move || picked.with(|p| p.contains(&word))
+    };
+    let outcome = move || match picked.with_untracked(|p| p.len()) {
+        1 => Outcome::Read,
+        _ => Outcome::NeededHelp,
+    };
+
+    let buttons = choices
+        .into_iter()
+        .map(|choice| {
+            let right = choice == word;
+            let class = {
+                let choice = choice.clone();
+                move || match (picked.with(|p| p.contains(&choice)), right) {
+                    (true, true) => "choice choice-right",
+                    (true, false) => "choice choice-wrong",
+                    (false, _) => "choice",
+                }
+            };
+            let pick = {
+                let (choice, answered) = (choice.clone(), answered.clone());
+                move |_| if !answered() && !picked.with_untracked(|p| p.contains(&choice)) {
+                    picked.update(|p| p.push(choice.clone()));
+                }
+            };
+            view! { <div class=class on:click=pick>{choice}</div> }
+        })
+        .collect_view();
+
+    view! {
+        <div class="choices">{buttons}</div>
+        {move || answered().then(|| view! {
+            <div class="next-word-button" on:click=move |_| ondone(outcome())>{move || Msg::Next.text(locale.get())}</div>
+        })}
+    }
+}
@@ -0,0 +1,125 @@
+use serde::{Serialize, Deserialize};
+
+use crate::app::ReadingMode;
+use crate::languages;
+use crate::outcomes::Outcome;
+
+/// Days until a word comes back, for each Leitner box. Box 1 words come back in the next run, even on the same day.
+const BOX_INTERVAL_DAYS: [i64; 5] = [0, 1, 3, 7, 14];
+
+/// A word (or sentence) the child needed help with, waiting to be read again.
+/// Reading it alone moves it up a box and spaces out the next review; needing help sends it back to box 1.
+/// Words read alone from the last box are dropped from the deck.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ReviewCard {
+    pub text: String,
+    pub reading_mode: ReadingMode,
+    /// Leitner box, from 1 to the number of boxes.
+    pub box_number: usize,
+    /// Local day number (see [`crate::scores::today`]) the word is due again.
+    pub due_day: i64,
+    /// Code of the language the word is in, empty for cards from before languages existed (the default one).
+    #[serde(default)]
+    pub language: String,
+}
+
+impl ReviewCard {
+    /// Whether the card is for `text` read in `reading_mode` and `language`.
+    pub fn is_for(&self, text: &str, reading_mode: &ReadingMode, language: &str) -> bool {
+        self.text == text && self.reading_mode == *reading_mode && languages::same_language(&self.language, language)
+    }
+}
+
+/// Moves a word between boxes after it was read. Words that are not in the deck only get in by needing help.
+pub fn schedule(deck: &mut Vec<ReviewCard>, text: &str, reading_mode: &ReadingMode, language: &str, outcome: Outcome, today: i64) {
+    let index = deck.iter().position(|c| c.is_for(text, reading_mode, language));
+
+    match (outcome, index) {
+        (Outcome::NeededHelp, Some(i)) => {
+            deck[i].box_number = 1;
+            deck[i].due_day = today + BOX_INTERVAL_DAYS[0];
+        },
+        (Outcome::NeededHelp, None) => deck.push(ReviewCard {
+            text: text.to_string(),
+            reading_mode: reading_mode.clone(),
+            box_number: 1,
+            due_day: today + BOX_INTERVAL_DAYS[0],
+            language: language.to_string(),
+        }),
+        (Outcome::Read, Some(i)) if deck[i].box_number >= BOX_INTERVAL_DAYS.len() => {
+            deck.remove(i);
+        },
+        (Outcome::Read, Some(i)) => {
+            deck[i].box_number += 1;
+            deck[i].due_day = today + BOX_INTERVAL_DAYS[deck[i].box_number - 1];
+        },
+        (Outcome::Read, None) => {},
+    }
+}
+
+/// Words in `language` due for review by `today`, most overdue (then lowest box) first.
+pub fn due(deck: &[ReviewCard], reading_mode: &ReadingMode, language: &str, today: i64) -> Vec<String> {
+    let mut due: Vec<&ReviewCard> = deck
+        .iter()
+        .filter(|c| c.reading_mode == *reading_mode && languages::same_language(&c.language, language) && c.due_day <= today)
+        .collect();
+    due.sort_by_key(|c| (c.due_day, c.box_number));
+    due.into_iter().map(|c| c.text.clone()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn card<'a>(deck: &'a [ReviewCard], text: &str) -> Option<&'a ReviewCard> {
+        deck.iter().find(|c| c.text == text)
+    }
+
+    #[test]
+    fn test_schedule() {
+        let mut deck = vec![];
+        let words = ReadingMode::Words;
+
+        schedule(&mut deck, "pato", &words, "pt", Outcome::Read, 10);
+        assert!(deck.is_empty(), "words read alone do not need reviewing");
+
+        schedule(&mut deck, "palhinha", &words, "pt", Outcome::NeededHelp, 10);
+        assert_eq!(card(&deck, "palhinha").map(|c| (c.box_number, c.due_day)), Some((1, 10)));
+
+        schedule(&mut deck, "palhinha", &words, "pt", Outcome::Read, 10);
+        schedule(&mut deck, "palhinha", &words, "pt", Outcome::Read, 11);
+        assert_eq!(card(&deck, "palhinha").map(|c| (c.box_number, c.due_day)), Some((3, 14)));
+
+        schedule(&mut deck, "palhinha", &words, "pt", Outcome::NeededHelp, 14);
+        assert_eq!(card(&deck, "palhinha").map(|c| (c.box_number, c.due_day)), Some((1, 14)), "needing help again starts over");
+
+        for day in 15..20 {
+            schedule(&mut deck, "palhinha", &words, "pt", Outcome::Read, day);
+        }
+        assert!(deck.is_empty(), "read alone from the last box, the word is learnt");
+
+        schedule(&mut deck, "palhinha", &ReadingMode::Sentences, "pt", Outcome::NeededHelp, 20);
+        schedule(&mut deck, "palhinha", &words, "pt", Outcome::Read, 20);
+        assert_eq!(deck.len(), 1, "words and sentences are reviewed separately");
+
+        schedule(&mut deck, "palhinha", &ReadingMode::Sentences, "en", Outcome::Read, 20);
+        assert_eq!(card(&deck, "palhinha").map(|c| c.box_number), Some(1), "each language keeps its own cards");
+    }
+
+    #[test]
+    fn test_due() {
+        let words = ReadingMode::Words;
+        let deck = vec![
+            ReviewCard { text: "chá".to_string(), reading_mode: words.clone(), box_number: 2, due_day: 5, language: String::new() },
+            ReviewCard { text: "carro".to_string(), reading_mode: words.clone(), box_number: 1, due_day: 5, language: String::new() },
+            ReviewCard { text: "baía".to_string(), reading_mode: words.clone(), box_number: 3, due_day: 3, language: "pt".to_string() },
+            ReviewCard { text: "manhã".to_string(), reading_mode: words.clone(), box_number: 1, due_day: 6, language: String::new() },
+            ReviewCard { text: "ship".to_string(), reading_mode: words.clone(), box_number: 1, due_day: 1, language: "en".to_string() },
+            ReviewCard { text: "O pato nada.".to_string(), reading_mode: ReadingMode::Sentences, box_number: 1, due_day: 1, language: String::new() },
+        ];
+
+        assert_eq!(due(&deck, &words, "pt", 5), vec!["baía", "carro", "chá"], "cards without a language are in the default one");
+        assert!(due(&deck, &words, "pt", 2).is_empty());
+        assert_eq!(due(&deck, &words, "en", 5), vec!["ship"]);
+    }
+}
@@ -0,0 +1,50 @@
+use leptos::*;
+
+use crate::i18n::{self, Msg};
+use crate::scores::{self, RunRecord};
+
+/// Shown at the end of every run: what was read, streaks and the best runs played with the same settings.
+#[component]
+pub fn run_results(run: RunRecord, new_record: bool, history: Signal<Vec<RunRecord>>, needed_help: Vec<String>, #[prop(into)] ondone: Callback<()>) -> impl IntoView {
+    let locale = i18n::use_locale().locale;
+    let current_streak = move || history.with(|h| scores::current_streak(h, scores::today()));
+    let best_streak = move || history.with(|h| scores::best_streak(h));
+    let leaderboard = history.with_untracked(|h| {
+        scores::leaderboard(h, &run, 5)
+            .into_iter()
+            .map(|r| {
+                let (words_per_minute, words_read) = (r.words_per_minute(), r.words_read);
+                view! {
+                    <li class:current-run={*r == run}>{move || i18n::leaderboard_text(locale.get(), words_per_minute, words_read)}</li>
+                }
+            })
+            .collect_view()
+    });
+
+    let summary = {
+        let run = run.clone();
+        move || i18n::run_summary_text(locale.get(), &run.game_mode, run.words_read, run.duration_secs)
+    };
+
+    let needed_help_view = match needed_help.is_empty() {
+        true => ().into_view(),
+        false => view! { <div class="needed-help">{move || Msg::ToPractise.text(locale.get())}{needed_help.join(", ")}</div> }.into_view(),
+    };
+
+    view! {
+        <h1 class="settings-title">{move || Msg::Finished.text(locale.get())}</h1>
+        <div class="run-results">
+            <div>{summary}</div>
+            <div><span style="font-weight: bold;">{run.words_per_minute()}</span><span>{move || Msg::WordsPerMinute.text(locale.get())}</span></div>
+            <div class="new-record" style:display=if new_record { "block" } else { "none" }>{move || Msg::NewRecord.text(locale.get())}</div>
+            {needed_help_view}
+            <div><span>{move || Msg::DaysInARow.text(locale.get())}</span><span style="font-weight: bold;">{current_streak}</span><span>{move || Msg::BestStreak.text(locale.get())}</span>{best_streak}<span>")"</span></div>
+        </div>
+        <div class="flex-center">
+            <ol class="leaderboard">{leaderboard}</ol>
+        </div>
+        <div class="flex-center">
+            <div class="start-button" on:click=move |_| ondone(())>{move || Msg::BackButton.text(locale.get())}</div>
+        </div>
+    }
+}
@@ -0,0 +1,161 @@
+use serde::{Serialize, Deserialize};
+
+use crate::app::{Difficulty, GameMode, ReadingMode};
+use crate::languages;
+
+const MILLIS_PER_DAY: f64 = 86_400_000.0;
+
+/// A finished run, as kept in the local run history.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RunRecord {
+    pub game_mode: GameMode,
+    pub reading_mode: ReadingMode,
+    pub difficulty: Difficulty,
+    /// `None` when the run was played with every word allowed.
+    pub allowed_chars: Option<String>,
+    pub words_read: usize,
+    pub duration_secs: u32,
+    /// Local calendar day the run finished on, counted in days since the unix epoch.
+    pub day: i64,
+    /// When the run finished, as a js timestamp. Tells apart otherwise identical runs when syncing devices.
+    #[serde(default)]
+    pub finished_at: f64,
+    /// Code of the language read, empty for runs played before languages existed (the default one).
+    #[serde(default)]
+    pub language: String,
+}
+
+impl RunRecord {
+    pub fn words_per_minute(&self) -> usize {
+        match self.duration_secs {
+            0 => 0,
+            secs => self.words_read * 60 / secs as usize,
+        }
+    }
+
+    /// Whether two runs were played under the same rules, in the same language, and so can be ranked against each other.
+    pub fn same_category(&self, other: &RunRecord) -> bool {
+        self.game_mode == other.game_mode
+            && self.reading_mode == other.reading_mode
+            && self.difficulty == other.difficulty
+            && languages::same_language(&self.language, &other.language)
+    }
+}
+
+/// Converts a js timestamp and the browser timezone offset (in minutes, as given by `Date.getTimezoneOffset`) into a local day number.
+pub fn day_from_millis(millis: f64, timezone_offset_mins: f64) -> i64 {
+    ((millis - timezone_offset_mins * 60_000.0) / MILLIS_PER_DAY).floor() as i64
+}
+
+/// The local day number for right now. Only meaningful in the browser.
+pub fn today() -> i64 {
+    let now = js_sys::Date::new_0();
+    day_from_millis(now.get_time(), now.get_timezone_offset())
+}
+
+fn played_days(history: &[RunRecord]) -> Vec<i64> {
+    let mut days: Vec<i64> = history.iter().map(|r| r.day).collect();
+    days.sort();
+    days.dedup();
+    days
+}
+
+/// Number of consecutive days with at least one run, ending today.
+/// A streak is still alive if the last run was yesterday, since the child may not have played yet today.
+pub fn current_streak(history: &[RunRecord], today: i64) -> usize {
+    let days = played_days(history);
+    let mut expected = match days.last() {
+        Some(last) if *last == today || *last == today - 1 => *last,
+        _ => return 0,
+    };
+
+    days.iter()
+        .rev()
+        .take_while(|d| {
+            let in_streak = **d == expected;
+            expected -= 1;
+            in_streak
+        })
+        .count()
+}
+
+/// Longest run of consecutive played days ever.
+pub fn best_streak(history: &[RunRecord]) -> usize {
+    if history.is_empty() {
+        return 0;
+    }
+
+    played_days(history)
+        .windows(2)
+        .fold((1, 1), |(best, current), pair| {
+            let current = if pair[1] == pair[0] + 1 { current + 1 } else { 1 };
+            (best.max(current), current)
+        })
+        .0
+}
+
+/// The best runs of the same category as `run`, fastest first.
+pub fn leaderboard<'a>(history: &'a [RunRecord], run: &RunRecord, size: usize) -> Vec<&'a RunRecord> {
+    let mut runs: Vec<&RunRecord> = history.iter().filter(|r| r.same_category(run)).collect();
+    runs.sort_by(|a, b| b.words_per_minute().cmp(&a.words_per_minute()).then(b.words_read.cmp(&a.words_read)));
+    runs.truncate(size);
+    runs
+}
+
+/// Whether `run` beats every earlier run of the same category. `history` must not contain `run` yet.
+pub fn is_high_score(history: &[RunRecord], run: &RunRecord) -> bool {
+    run.words_read > 0
+        && history
+            .iter()
+            .filter(|r| r.same_category(run))
+            .all(|r| r.words_per_minute() < run.words_per_minute())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::run_on;
+
+    #[test]
+    fn test_day_from_millis() {
+        // 2024-01-01T23:30:00Z
+        let millis = 1_704_151_800_000.0;
+        assert_eq!(day_from_millis(millis, 0.0), 19723);
+        // one hour ahead of UTC (offset is negative) is already the next day
+        assert_eq!(day_from_millis(millis, -60.0), 19724);
+    }
+
+    #[test]
+    fn test_streaks() {
+        let history = [ run_on(10, 10, 60), run_on(11, 10, 60), run_on(11, 10, 60), run_on(12, 10, 60), run_on(20, 10, 60), run_on(21, 10, 60) ];
+
+        assert_eq!(current_streak(&history, 21), 2);
+        assert_eq!(current_streak(&history, 22), 2, "streak is kept until the end of the next day");
+        assert_eq!(current_streak(&history, 23), 0);
+        assert_eq!(current_streak(&[], 23), 0);
+        assert_eq!(best_streak(&history), 3);
+        assert_eq!(best_streak(&[]), 0);
+        assert_eq!(best_streak(&[run_on(5, 10, 60), run_on(5, 10, 60)]), 1, "several runs on one day are a one day streak");
+    }
+
+    #[test]
+    fn test_high_scores() {
+        let history = [ run_on(1, 10, 60), run_on(2, 10, 30), run_on(3, 10, 120) ];
+
+        assert!(is_high_score(&history, &run_on(4, 30, 60)));
+        assert!(!is_high_score(&history, &run_on(4, 20, 60)), "ties do not count as a new record");
+        assert!(is_high_score(&[], &run_on(4, 1, 60)));
+
+        let mut time_attack = run_on(4, 100, 60);
+        time_attack.game_mode = GameMode::TimeAttack;
+        assert!(leaderboard(&history, &time_attack, 5).is_empty());
+
+        let board: Vec<usize> = leaderboard(&history, &run_on(4, 0, 0), 2).iter().map(|r| r.words_per_minute()).collect();
+        assert_eq!(board, vec![20, 10]);
+
+        let english = RunRecord { language: "en".to_string(), ..run_on(4, 5, 60) };
+        assert!(is_high_score(&history, &english), "each language keeps its own records");
+        assert!(leaderboard(&history, &english, 5).is_empty());
+        assert_eq!(leaderboard(&history, &RunRecord { language: "pt".to_string(), ..run_on(4, 0, 0) }, 5).len(), 3, "runs from before languages are in the default one");
+    }
+}
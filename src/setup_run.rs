@@ -2,19 +2,25 @@ use leptos::*;
 use leptos_meta::*;
 use leptos_use::storage::{use_local_storage, JsonCodec};
 
-use crate::app::{ Difficulty, RunSettings};
-use crate::lexicanum;
+use crate::app::{ fetch_pool, Difficulty, GameMode, PoolOptions, ReadingMode, RunSettings};
+use crate::curriculum::{self, Lesson};
+use crate::dictionary::{self, DictionaryProvider};
+use crate::i18n::{self, Msg};
+use crate::languages::{self, Language};
+use crate::lexicanum::{self, WordPoolError};
+use crate::profiles;
+use crate::progress::{self, ProfileSettings};
+use crate::word_index;
 
 #[derive(Clone, Debug, PartialEq)]
-struct ToggleableKey<T> {
+struct ToggleableKey {
     id: String,
     label: String,
-    value: T,
     is_active: Signal<bool>,
     set_active: WriteSignal<bool>,
 }
 
-fn difficulty_assets(diff: Difficulty) -> String {
+pub fn difficulty_assets(diff: Difficulty) -> String {
     match diff {
         Difficulty::Easiest => "assets/progress_bars-01.svg",
         Difficulty::Easy => "assets/progress_bars-02.svg",
@@ -26,45 +32,217 @@ fn difficulty_assets(diff: Difficulty) -> String {
 
 #[component]
 pub fn setup_run(settings: RunSettings, #[prop(into)] onready: Callback<i32>) -> impl IntoView {
+    let locale = i18n::use_locale().locale;
     let get_server_words = create_action(
-        move |options: &(Option<String>, usize, Difficulty)| {
+        move |options: &PoolOptions| {
             let cloned_options = options.clone();
             logging::log!("calling server for words");
-            async move { lexicanum::get_word_pool(cloned_options.0, cloned_options.1, cloned_options.2).await }
+            async move { fetch_pool(cloned_options).await }
         }
     );
 
-    let start_new_run = move |_| { 
-        let filter = match settings.all_words.get() {
-            true => None,
-            false=> Some(settings.allowed_chars.get()),
+    let language = Signal::derive(move || settings.current_language());
+    let matching_count = create_local_resource(
+        move || (language.get().code.to_string(), settings.all_words.get(), settings.allowed_chars.get(), settings.difficulty.get(), settings.reading_mode.get()),
+        move |(language, all_words, allowed_chars, diff, reading_mode)| async move {
+            let filter = match all_words {
+                true => None,
+                false => Some(allowed_chars),
+            };
+            word_index::with_local_fallback(
+                lexicanum::count_matching(language.clone(), filter.clone(), diff.clone(), reading_mode.clone()),
+                word_index::local_count(language, filter, diff, reading_mode),
+            ).await
+        }
+    );
+    let no_matches = move || matches!(matching_count.get(), Some(Ok(0)));
+    let matching_count_view = move || match matching_count.get() {
+        Some(Ok(count)) => i18n::matching_count_text(locale.get(), count, settings.num_words.get(), &settings.reading_mode.get()),
+        _ => "".to_string(),
+    };
+
+    let (letters_preset, set_letters_preset) = create_signal(None::<Vec<String>>);
+
+    // settings travel with the profile to other devices: the copy changed last wins
+    let (settings_synced_at, set_settings_synced_at, _) = use_local_storage::<f64, JsonCodec>(profiles::storage_key(settings.profile_id, "settings_synced_at"));
+    let stored_settings = create_local_resource(|| (), move |_| async move { progress::load_stored_settings(settings.profile_id).await });
+    create_effect(move |_| match stored_settings.get() {
+        Some(Ok(Some(stored))) if stored.updated_at > settings_synced_at.get_untracked() => {
+            set_letters_preset.set(Some(stored.letters));
+            settings.set_all_words.set(stored.all_words);
+            settings.set_difficulty.set(stored.difficulty);
+            settings.set_reading_mode.set(stored.reading_mode);
+            settings.set_game_mode.set(stored.game_mode);
+            settings.set_time_limit_secs.set(stored.time_limit_secs);
+            settings.set_show_syllables.set(stored.show_syllables);
+            settings.set_lesson.set(stored.lesson);
+            settings.set_language.set(stored.language);
+            settings.set_listen_after_trying.set(stored.listen_after_trying);
+            settings.set_dictionary.set(stored.dictionary);
+            settings.set_with_pictures.set(stored.with_pictures);
+            settings.set_picture_after_reading.set(stored.picture_after_reading);
+            set_settings_synced_at.set(stored.updated_at);
+        },
+        Some(Err(e)) => progress::log_sync_error("settings", &e),
+        _ => {},
+    });
+    let sync_settings = move || {
+        let stored = ProfileSettings {
+            letters: untrack(|| settings.current_language()).letters_in(&settings.allowed_chars.get_untracked()),
+            all_words: settings.all_words.get_untracked(),
+            difficulty: settings.difficulty.get_untracked(),
+            reading_mode: settings.reading_mode.get_untracked(),
+            game_mode: settings.game_mode.get_untracked(),
+            time_limit_secs: settings.time_limit_secs.get_untracked(),
+            show_syllables: settings.show_syllables.get_untracked(),
+            lesson: settings.lesson.get_untracked(),
+            language: settings.language.get_untracked(),
+            listen_after_trying: settings.listen_after_trying.get_untracked(),
+            dictionary: settings.dictionary.get_untracked(),
+            with_pictures: settings.with_pictures.get_untracked(),
+            picture_after_reading: settings.picture_after_reading.get_untracked(),
+            updated_at: js_sys::Date::now(),
         };
-        get_server_words.dispatch((filter, settings.num_words.get(), settings.difficulty.get()));
+        set_settings_synced_at.set(stored.updated_at);
+        spawn_local(async move {
+            if let Err(e) = progress::store_settings(settings.profile_id, stored).await {
+                progress::log_sync_error("settings", &e);
+            }
+        });
+    };
+
+    let start_new_run = move |_| { 
+        if no_matches() {
+            return;
+        }
+        sync_settings();
+        get_server_words.dispatch(settings.pool_options());
     };
     
     let keyboard_visible = Signal::derive( move || !settings.all_words.get());
 
+    // only the languages the server has words for are offered, and only when there is more than one
+    let available_languages = create_local_resource(|| (), |_| async move {
+        word_index::with_local_fallback(lexicanum::get_languages(), word_index::local_languages()).await
+    });
+    let pick_language = move |language: &'static Language| {
+        settings.set_lesson.set(None);
+        settings.set_language.set(language.code.to_string());
+    };
+    let languages_view = move || match available_languages.get() {
+        Some(Ok(codes)) if codes.len() > 1 => codes
+            .iter()
+            .filter_map(|code| languages::find_language(code))
+            .map(|l| view! {
+                <div class="key" class:active=move || language.get() == l on:click=move |_| pick_language(l)>{l.name}</div>
+            })
+            .collect_view(),
+        _ => ().into_view(),
+    };
+
+    let lessons = create_local_resource(move || language.get().code.to_string(), |language| async move { curriculum::fetch_lessons(language).await });
+    let pick_lesson = move |lesson: Lesson| {
+        settings.set_all_words.set(false);
+        set_letters_preset.set(Some(lesson.letters.clone()));
+        settings.set_lesson.set(Some(lesson));
+    };
+    let lessons_view = move || match lessons.get() {
+        Some(Ok(lessons)) => lessons
+            .into_iter()
+            .map(|lesson| {
+                let number = lesson.number;
+                let is_active = move || settings.active_lesson().is_some_and(|l| l.number == number);
+                let (name, letters) = (lesson.name.clone(), lesson.letters.join(" "));
+                view! {
+                    <div class="key" class:active=is_active title=letters on:click=move |_| pick_lesson(lesson.clone())>{name}</div>
+                }
+            })
+            .collect_view(),
+        _ => ().into_view(),
+    };
+    let new_letters_view = move || settings.active_lesson().map(|lesson| {
+        i18n::lesson_text(locale.get(), lesson.number, &lesson.focus())
+    });
+
+    let custom_dictionary = move || match settings.dictionary.get() {
+        DictionaryProvider::Url(template) => Some(template),
+        _ => None,
+    };
+    let pick_dictionary = move |provider: DictionaryProvider| settings.set_dictionary.set(provider);
+
+    let error_message = move || match get_server_words.value().get() {
+        Some(Err(e)) => Some(match WordPoolError::from_server_fn_error(&e) {
+            Some(pool_error) => pool_error.message(locale.get()),
+            None => Msg::CouldNotFetchWords.text(locale.get()),
+        }),
+        _ => None,
+    };
+
     create_effect(move |_| {
-        if let Some(Ok(word_pool)) = get_server_words.value().get() {
-            logging::log!("words file was loaded. {} words retrieved", word_pool.len());
-            settings.word_pool.set(word_pool);
-            onready.call(1);
+        match get_server_words.value().get() {
+            Some(Ok(word_pool)) => {
+                logging::log!("words file was loaded. {} words retrieved", word_pool.len());
+                settings.word_pool.set(word_pool);
+                onready.call(1);
+            },
+            Some(Err(e)) => logging::log!("could not get words: {}", e),
+            None => {},
         }
     });
 
     view! {
-        <h1 class="settings-title"> "Vamos Ler!"</h1>
+        <h1 class="settings-title">{move || Msg::AppTitle.text(locale.get())}</h1>
         <div class="flex-center">
             <div class="settings-section">
-                <div class="allowed-letters-title">letras permitidas: </div>  
+                <div class="settings-language">{languages_view}</div>
+                <div class="allowed-letters-title">{move || Msg::AllowedLetters.text(locale.get())}</div>  
                 <div>
-                    <ToggleKeyboard is_visible=keyboard_visible set_all_values = settings.set_allowed_chars > </ToggleKeyboard>
+                    <ToggleKeyboard is_visible=keyboard_visible set_all_values = settings.set_allowed_chars preset=letters_preset language=language profile_id=settings.profile_id > </ToggleKeyboard>
                 </div>
+                <div class="matching-words" class:no-matches=no_matches>{matching_count_view}</div>
+                <div class="lessons-title">{move || Msg::Lessons.text(locale.get())}</div>
+                <div class="settings-lessons">{lessons_view}</div>
+                <div class="lesson-new-letters">{new_letters_view}</div>
                 <div class="all-words">
-                    <ToggleKey label="Todas".to_string() is_active=settings.all_words set_active=settings.set_all_words />
+                    <ToggleKey label=Signal::derive(move || Msg::AllLetters.text(locale.get()).to_string()) is_active=settings.all_words set_active=settings.set_all_words />
+                </div>
+                <div class="show-syllables" style:display=move || if settings.current_language().syllabifier.is_some() { "block" } else { "none" }>
+                    <ToggleKey label=Signal::derive(move || Msg::Syllables.text(locale.get()).to_string()) is_active=settings.show_syllables set_active=settings.set_show_syllables />
+                </div>
+                <div class="listen-after-trying">
+                    <ToggleKey label=Signal::derive(move || Msg::ListenAfterTrying.text(locale.get()).to_string()) is_active=settings.listen_after_trying set_active=settings.set_listen_after_trying />
+                </div>
+                <div class="with-pictures">
+                    <ToggleKey label=Signal::derive(move || Msg::WithPictures.text(locale.get()).to_string()) is_active=settings.with_pictures set_active=settings.set_with_pictures />
+                    <ToggleKey label=Signal::derive(move || Msg::PictureAfterReading.text(locale.get()).to_string()) is_active=settings.picture_after_reading set_active=settings.set_picture_after_reading />
+                </div>
+                <div class="lessons-title">{move || Msg::Dictionary.text(locale.get())}</div>
+                <div class="settings-dictionary">
+                    <div class="key" class:active=move || settings.dictionary.get() == DictionaryProvider::Language on:click=move |_| pick_dictionary(DictionaryProvider::Language)>{move || Msg::DictionaryOnline.text(locale.get())}</div>
+                    <div class="key" class:active=move || custom_dictionary().is_some() on:click=move |_| if custom_dictionary().is_none() { pick_dictionary(DictionaryProvider::Url(String::new())) }>{move || Msg::DictionaryOther.text(locale.get())}</div>
+                    <div class="key" class:active=move || settings.dictionary.get() == DictionaryProvider::Local on:click=move |_| pick_dictionary(DictionaryProvider::Local)>{move || Msg::DictionaryLocal.text(locale.get())}</div>
+                </div>
+                <input class="dictionary-url" type="url" placeholder=dictionary::URL_TEMPLATE_EXAMPLE
+                    style:display=move || if custom_dictionary().is_some() { "block" } else { "none" }
+                    prop:value=move || custom_dictionary().unwrap_or_default()
+                    on:change=move |ev| pick_dictionary(DictionaryProvider::Url(event_target_value(&ev).trim().to_string())) />
+                <div class="settings-reading-mode">
+                    <div class="key" class:active=move || settings.reading_mode.get() == ReadingMode::Words on:click=move |_| settings.set_reading_mode.set(ReadingMode::Words)>{move || Msg::Words.text(locale.get())}</div>
+                    <div class="key" class:active=move || settings.reading_mode.get() == ReadingMode::Sentences on:click=move |_| settings.set_reading_mode.set(ReadingMode::Sentences)>{move || Msg::Sentences.text(locale.get())}</div>
+                    <div class="key" class:active=move || settings.reading_mode.get() == ReadingMode::Spelling on:click=move |_| settings.set_reading_mode.set(ReadingMode::Spelling)>{move || Msg::Spelling.text(locale.get())}</div>
+                    <div class="key" class:active=move || settings.reading_mode.get() == ReadingMode::Recognition on:click=move |_| settings.set_reading_mode.set(ReadingMode::Recognition)>{move || Msg::Recognition.text(locale.get())}</div>
+                </div>
+                <div class="settings-game-mode">
+                    <div class="key" class:active=move || settings.game_mode.get() == GameMode::Classic on:click=move |_| settings.set_game_mode.set(GameMode::Classic)>{move || Msg::Classic.text(locale.get())}</div>
+                    <div class="key" class:active=move || settings.game_mode.get() == GameMode::TimeAttack on:click=move |_| settings.set_game_mode.set(GameMode::TimeAttack)>{move || Msg::TimeAttack.text(locale.get())}</div>
+                </div>
+                <div class="settings-time-limit" style:display=move || if settings.game_mode.get() == GameMode::TimeAttack { "flex" } else { "none" }>
+                    {[30, 60, 120].into_iter().map(|secs| view! {
+                        <div class="key" class:active=move || settings.time_limit_secs.get() == secs on:click=move |_| settings.set_time_limit_secs.set(secs)>{secs}"s"</div>
+                    }).collect_view()}
                 </div>
                     <div class="settings-difficulty">
-                        <div class="difficulty-title"> "Dificuldade"</div>
+                        <div class="difficulty-title">{move || Msg::Difficulty.text(locale.get())}</div>
                         <div class="difficulty-slider"><img prop:src=move || settings.difficulty.with( |diff| difficulty_assets(diff.clone())) ></img></div>
                         <div>
                             <span> <input type="radio" prop:checked=move || settings.difficulty.with( |diff| *diff == Difficulty::Easiest) on:input = move |_e| {settings.set_difficulty.set(Difficulty::Easiest)} /></span>
@@ -76,68 +254,57 @@ pub fn setup_run(settings: RunSettings, #[prop(into)] onready: Callback<i32>) ->
                     </div>
             </div>
         </div>
+        <div class="setup-error" style:display=move || if error_message().is_some() { "block" } else { "none" }>{error_message}</div>
         <div class="flex-center">
-            <div class="start-button" on:click=start_new_run>"COMEÇAR"</div>
+            <div class="start-button" class:disabled=no_matches on:click=start_new_run>{move || Msg::Start.text(locale.get())}</div>
         </div>
     }
 }
 
+/// The letters of the setup keyboard. Only the keys of `language` are shown, and the letters switched on
+/// are turned into a filter with that language's variants of each letter.
 #[component]
-pub fn toggle_keyboard(set_all_values: WriteSignal<String>, is_visible: Signal<bool>) -> impl IntoView {
-    let ids_labels_vals = vec![
-        ("tk_A", "A", "aAàÀáÁâÂãÃ"),
-        ("tk_E", "E", "eEèÈéÉêÊ"),
-        ("tk_I", "I", "iIíÍ"),
-        ("tk_O", "O", "oOôÔòÒóÓõÕ"),
-        ("tk_U", "U", "uUùÙúÚ"),
-        ("tk_B", "B", "bB"),
-        ("tk_C", "C", "cCÇ"),
-        ("tk_D", "D", "dD"),
-        ("tk_F", "F", "fF"),
-        ("tk_G", "G", "gG"),
-        ("tk_H", "H", "hH"),
-        ("tk_J", "J", "jJ"),
-        ("tk_K", "K", "kK"),
-        ("tk_L", "L", "lL"),
-        ("tk_M", "M", "mM"),
-        ("tk_N", "N", "nN"),
-        ("tk_P", "P", "pP"),
-        ("tk_Q", "Q", "qQ"),
-        ("tk_R", "R", "rR"),
-        ("tk_S", "S", "sS"),
-        ("tk_T", "T", "tT"),
-        ("tk_V", "V", "vV"),
-        ("tk_W", "W", "wW"),
-        ("tk_X", "X", "xX"),
-        ("tk_Y", "Y", "yY"),
-        ("tk_Z", "Z", "zZ"),
-    ];
-
+pub fn toggle_keyboard(set_all_values: WriteSignal<String>, is_visible: Signal<bool>, #[prop(into)] preset: Signal<Option<Vec<String>>>, #[prop(into)] language: Signal<&'static Language>, profile_id: u64) -> impl IntoView {
     let (keys, set_keys) = create_signal(
-        ids_labels_vals
-        .iter()
-        .map(|(id,l,v)| { 
-            let (rs, ws, _) = use_local_storage::<bool, JsonCodec>(id); 
-            ToggleableKey { id: id.to_string(), label: l.to_string(), value: v.to_string(), is_active: rs, set_active: ws }
+        languages::keyboard_labels()
+        .into_iter()
+        .map(|l| { 
+            let id = format!("tk_{}", l);
+            let (rs, ws, _) = use_local_storage::<bool, JsonCodec>(profiles::storage_key(profile_id, &id)); 
+            ToggleableKey { id, label: l.to_string(), is_active: rs, set_active: ws }
         })
         .collect::<Vec<_>>());
 
+    // picking a lesson switches on exactly the letters it has taught so far
+    create_effect(move |_| {
+        if let Some(letters) = preset.get() {
+            keys.with_untracked(|keys| {
+                for k in keys {
+                    k.set_active.set(letters.iter().any(|l| k.label.eq_ignore_ascii_case(l)));
+                }
+            });
+        }
+    });
+
     let derived_all_values = create_effect( move |_| {
         keys.with( |vals| 
             set_all_values.set(
-                vals.iter()
-                    .filter(|k| k.is_active.get())
-                    .map(|k| k.value.clone() )
-                    .collect::<Vec<String>>()
-                    .join("")
+                language.get().allowed_chars_for(
+                    &vals.iter()
+                        .filter(|k| k.is_active.get())
+                        .map(|k| k.label.clone() )
+                        .collect::<Vec<String>>()
+                )
             )
         )
     });
 
+    let shown_keys = move || keys.get().into_iter().filter(|k| language.get().letter_variants(&k.label).is_some()).collect::<Vec<_>>();
+
     view! {
         <div class="keyboard" style:display=move || if is_visible.get() { "flex" } else { "none" } >
             <For 
-                each=keys
+                each=shown_keys
                 key=|key| key.id.clone()
                 children= move |key| {
                     view! {
@@ -150,7 +317,7 @@ pub fn toggle_keyboard(set_all_values: WriteSignal<String>, is_visible: Signal<b
 }
 
 #[component]
-fn toggle_key(label: String, is_active: Signal<bool>, set_active: WriteSignal<bool>) -> impl IntoView {
+fn toggle_key(#[prop(into)] label: MaybeSignal<String>, is_active: Signal<bool>, set_active: WriteSignal<bool>) -> impl IntoView {
     view!{
         <div class="key" class:active=is_active on:click= move |_| { set_active.set(!is_active.get()) }  >
             {label}
@@ -0,0 +1,85 @@
+use leptos::window;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::JsCast;
+use web_sys::{HtmlAudioElement, SpeechSynthesisErrorCode, SpeechSynthesisErrorEvent, SpeechSynthesisUtterance, SpeechSynthesisVoice};
+
+use crate::languages::Language;
+
+/// Recordings live under `assets/audio/<language code>/`, one per word or sentence, named by [`audio_key`].
+/// They are served with the other assets, so the static csr build and the offline cache get them too.
+pub const AUDIO_DIR: &str = "/assets/audio";
+
+/// Slower than normal speech, so a child can follow along letter by letter.
+const SPEECH_RATE: f32 = 0.8;
+
+/// The file name (without extension) of a word's or sentence's recording: lowercase, punctuation dropped and
+/// spaces turned into underscores, e.g. "O pato nada." becomes "o_pato_nada". Accents and hyphens are kept.
+pub fn audio_key(text: &str) -> String {
+    text.to_lowercase()
+        .split_whitespace()
+        .map(|w| w.chars().filter(|c| c.is_alphanumeric() || *c == '-').collect::<String>())
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+pub fn audio_url(language: &Language, text: &str) -> String {
+    format!("{}/{}/{}.mp3", AUDIO_DIR, language.code, js_sys::encode_uri_component(&audio_key(text)))
+}
+
+/// Reads the text out loud with the browser's speech synthesis, or with the recording from the server when
+/// the browser has no voice for the language or the synthesis fails.
+pub fn speak(language: &Language, text: &str) {
+    if !speak_with_synthesis(language, text) {
+        play_recording(&audio_url(language, text));
+    }
+}
+
+fn speak_with_synthesis(language: &Language, text: &str) -> bool {
+    let Ok(synthesis) = window().speech_synthesis() else {
+        return false;
+    };
+    // some browsers only list their voices once `voiceschanged` fired: until then, let them pick one by `lang`
+    let voices: Vec<SpeechSynthesisVoice> = synthesis.get_voices().iter().filter_map(|v| v.dyn_into().ok()).collect();
+    if !voices.is_empty() && !voices.iter().any(|v| v.lang().to_lowercase().starts_with(language.code)) {
+        return false;
+    }
+    let Ok(utterance) = SpeechSynthesisUtterance::new_with_text(text) else {
+        return false;
+    };
+
+    utterance.set_lang(language.speech_lang);
+    utterance.set_rate(SPEECH_RATE);
+    let recording = audio_url(language, text);
+    let on_error = Closure::once_into_js(move |ev: SpeechSynthesisErrorEvent| {
+        // being cut short by the next tap is not a failure
+        if !matches!(ev.error(), SpeechSynthesisErrorCode::Canceled | SpeechSynthesisErrorCode::Interrupted) {
+            play_recording(&recording);
+        }
+    });
+    utterance.set_onerror(Some(on_error.unchecked_ref()));
+    // a second tap starts over instead of queueing behind the first
+    synthesis.cancel();
+    synthesis.speak(&utterance);
+    true
+}
+
+fn play_recording(url: &str) {
+    if let Ok(audio) = HtmlAudioElement::new_with_src(url) {
+        // words without a recording just stay silent
+        let _ = audio.play();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_audio_key() {
+        assert_eq!(audio_key("Pato"), "pato");
+        assert_eq!(audio_key("O pato  nada."), "o_pato_nada");
+        assert_eq!(audio_key("guarda-chuva"), "guarda-chuva");
+        assert_eq!(audio_key("Já lês? "), "já_lês");
+    }
+}
@@ -0,0 +1,204 @@
+use leptos::*;
+use leptos::leptos_dom::helpers::TimeoutHandle;
+use std::time::Duration;
+
+use crate::i18n::{self, Msg};
+use crate::languages::Language;
+use crate::outcomes::Outcome;
+
+/// How long a key has to be held for its accented variants to show up.
+const HOLD: Duration = Duration::from_millis(450);
+
+/// A word being spelled letter by letter. Characters no key can type (hyphens, letters of other languages)
+/// are filled in by themselves.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Spelling {
+    language: &'static Language,
+    letters: Vec<char>,
+    /// How many letters are spelled so far.
+    typed: usize,
+    /// Wrong taps over the whole word.
+    mistakes: usize,
+    /// The last wrong tap, shown in place of the missing letter until the next tap.
+    wrong: Option<char>,
+}
+
+impl Spelling {
+    pub fn new(language: &'static Language, word: &str) -> Self {
+        let mut spelling = Spelling { language, letters: word.chars().collect(), typed: 0, mistakes: 0, wrong: None };
+        spelling.fill_in_untypable();
+        spelling
+    }
+
+    fn typable(&self, c: char) -> bool {
+        self.language.letter_of(c).is_some()
+    }
+
+    fn fill_in_untypable(&mut self) {
+        while self.letters.get(self.typed).is_some_and(|c| !self.typable(*c)) {
+            self.typed += 1;
+        }
+    }
+
+    /// Checks a tapped letter against the next one of the word, ignoring case. Accents have to match.
+    pub fn tap(&mut self, c: char) -> bool {
+        let Some(expected) = self.letters.get(self.typed) else {
+            return false;
+        };
+        match expected.to_lowercase().eq(c.to_lowercase()) {
+            true => {
+                self.typed += 1;
+                self.wrong = None;
+                self.fill_in_untypable();
+                true
+            },
+            false => {
+                self.mistakes += 1;
+                self.wrong = Some(c);
+                false
+            },
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.typed == self.letters.len()
+    }
+
+    /// Spelled without a single wrong tap counts as read alone.
+    pub fn outcome(&self) -> Outcome {
+        match self.mistakes {
+            0 => Outcome::Read,
+            _ => Outcome::NeededHelp,
+        }
+    }
+
+    /// What to show in place of each letter, with the class that colours it.
+    pub fn slots(&self) -> Vec<(String, &'static str)> {
+        self.letters
+            .iter()
+            .enumerate()
+            .map(|(i, c)| match (i, self.wrong) {
+                (i, _) if i < self.typed => (c.to_string(), "letter-spelled"),
+                (_, _) if !self.typable(*c) => (c.to_string(), "letter-given"),
+                (i, Some(wrong)) if i == self.typed => (wrong.to_string(), "letter-wrong"),
+                _ => ("_".to_string(), "letter-missing"),
+            })
+            .collect()
+    }
+}
+
+/// The word being spelled, one slot per letter, and the keyboard to spell it with. Once the word is spelled,
+/// the keyboard makes way for a button that hands the outcome to `ondone`.
+#[component]
+pub fn spelling_board(word: String, language: &'static Language, allowed_chars: Option<String>, #[prop(into)] ondone: Callback<Outcome>) -> impl IntoView {
+    let locale = i18n::use_locale().locale;
+    let spelling = create_rw_signal(Spelling::new(language, &word));
+    let on_letter = Callback::new(move |c: char| spelling.update(|s| { s.tap(c); }));
+
+    view! {
+        <div class="spelled-word">
+            {move || spelling.with(|s| s.slots()).into_iter().map(|(text, class)| view! { <span class=class>{text}</span> }).collect_view()}
+        </div>
+        {move || match spelling.with(|s| s.is_done()) {
+            true => view! {
+                <div class="next-word-button" on:click=move |_| ondone(spelling.with_untracked(|s| s.outcome()))>{move || Msg::Next.text(locale.get())}</div>
+            }.into_view(),
+            false => view! { <PlayKeyboard language=language allowed_chars=allowed_chars.clone() on_letter=on_letter /> }.into_view(),
+        }}
+    }
+}
+
+/// The letters switched on in setup (every letter with `None`), as keys that type their plain letter.
+/// Holding a key with accented variants opens a row to pick one of them.
+#[component]
+pub fn play_keyboard(language: &'static Language, allowed_chars: Option<String>, on_letter: Callback<char>) -> impl IntoView {
+    let held = store_value(None::<TimeoutHandle>);
+    let picking = create_rw_signal(None::<Vec<char>>);
+
+    let cancel_hold = move || {
+        if let Some(handle) = held.get_value() {
+            handle.clear();
+            held.set_value(None);
+        }
+    };
+    let press = move |variants: Vec<char>| {
+        cancel_hold();
+        picking.set(None);
+        let open_variants = move || {
+            held.set_value(None);
+            picking.set(Some(variants));
+        };
+        held.set_value(set_timeout_with_handle(open_variants, HOLD).ok());
+    };
+    // a release before the variants opened is a plain tap
+    let release = move |letter: char| {
+        if held.get_value().is_some() {
+            cancel_hold();
+            on_letter(letter);
+        }
+    };
+
+    let keys = language
+        .letter_groups
+        .iter()
+        .filter(|(_, variants)| allowed_chars.as_ref().is_none_or(|allowed| allowed.contains(variants)))
+        .map(|(label, _)| (*label, language.key_variants(label)))
+        .filter(|(_, variants)| !variants.is_empty())
+        .map(|(label, variants)| {
+            let letter = variants[0];
+            match variants.len() {
+                1 => view! { <div class="key play-key" on:click=move |_| on_letter(letter)>{label}</div> },
+                _ => view! {
+                    <div class="key play-key"
+                        on:pointerdown=move |_| press(variants.clone())
+                        on:pointerup=move |_| release(letter)
+                        on:pointerleave=move |_| cancel_hold()
+                        on:contextmenu=|ev| ev.prevent_default()>
+                        {label}
+                    </div>
+                },
+            }
+        })
+        .collect_view();
+
+    view! {
+        <div class="variant-picker">
+            {move || picking.get().unwrap_or_default().into_iter().map(|c| view! {
+                <div class="key" on:click=move |_| { picking.set(None); on_letter(c) }>{c.to_string()}</div>
+            }).collect_view()}
+        </div>
+        <div class="play-keyboard">{keys}</div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::languages::PORTUGUESE;
+
+    fn shown(spelling: &Spelling) -> String {
+        spelling.slots().into_iter().map(|(text, _)| text).collect()
+    }
+
+    #[test]
+    fn test_spelling() {
+        let mut spelling = Spelling::new(&PORTUGUESE, "Pá");
+        assert_eq!(shown(&spelling), "__");
+        assert!(spelling.tap('p'), "case does not matter");
+        assert!(!spelling.tap('a'), "accents do");
+        assert_eq!(spelling.slots()[1], ("a".to_string(), "letter-wrong"));
+        assert!(spelling.tap('á'));
+        assert!(spelling.is_done());
+        assert_eq!(shown(&spelling), "Pá");
+        assert_eq!(spelling.outcome(), Outcome::NeededHelp);
+        assert!(!spelling.tap('a'), "nothing left to spell");
+
+        let mut spelling = Spelling::new(&PORTUGUESE, "pá-pá");
+        assert_eq!(shown(&spelling), "__-__", "hyphens are given");
+        for c in ['p', 'á', 'p', 'á'] {
+            spelling.tap(c);
+        }
+        assert!(spelling.is_done());
+        assert_eq!(spelling.outcome(), Outcome::Read);
+    }
+}
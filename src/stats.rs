@@ -0,0 +1,264 @@
+use leptos::*;
+use leptos_router::*;
+use leptos_use::storage::{use_local_storage, JsonCodec};
+
+use crate::app::{Difficulty, ReadingMode};
+use crate::curriculum;
+use crate::i18n::{self, Msg};
+use crate::languages::{self, Language};
+use crate::outcomes::{Outcome, WordOutcome};
+use crate::profiles;
+use crate::scores::RunRecord;
+use crate::setup_run::difficulty_assets;
+
+/// Attempts needed before a letter's score is trusted.
+const MIN_ATTEMPTS: usize = 10;
+/// Share of words read alone, in percent, for a letter to count as learnt.
+const MASTERED_PERCENT: usize = 80;
+/// How many new letters to suggest at a time.
+const SUGGESTED_LETTERS: usize = 2;
+
+/// How often words with a letter or digraph were read without help.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mastery {
+    /// A keyboard letter (lowercase) or one of the hard digraphs.
+    pub unit: String,
+    pub read: usize,
+    pub attempts: usize,
+}
+
+impl Mastery {
+    pub fn percent(&self) -> usize {
+        match self.attempts {
+            0 => 0,
+            attempts => self.read * 100 / attempts,
+        }
+    }
+
+    pub fn is_trusted(&self) -> bool {
+        self.attempts >= MIN_ATTEMPTS
+    }
+}
+
+/// The keyboard letters and hard digraphs of `language` a word is made of, each once.
+fn units_in(word: &str, language: &Language) -> Vec<String> {
+    let lowercase = word.to_lowercase();
+    language.letter_groups
+        .iter()
+        .filter(|(_, variants)| word.chars().any(|c| variants.contains(c)))
+        .map(|(label, _)| label.to_lowercase())
+        .chain(language.hard_digraphs.iter().filter(|d| lowercase.contains(*d)).map(|d| d.to_string()))
+        .collect()
+}
+
+/// Scores every letter and digraph seen in single words of `language`, letters in keyboard order followed by the digraphs.
+/// Sentences are left out: needing help with one says little about any single letter in it.
+pub fn mastery(outcomes: &[WordOutcome], language: &Language) -> Vec<Mastery> {
+    let mut scores: Vec<Mastery> = language.letter_groups
+        .iter()
+        .map(|(label, _)| label.to_lowercase())
+        .chain(language.hard_digraphs.iter().map(|d| d.to_string()))
+        .map(|unit| Mastery { unit, read: 0, attempts: 0 })
+        .collect();
+
+    for o in outcomes.iter().filter(|o| o.reading_mode == ReadingMode::Words && languages::same_language(&o.language, language.code)) {
+        for unit in units_in(&o.text, language) {
+            if let Some(score) = scores.iter_mut().find(|s| s.unit == unit) {
+                score.attempts += 1;
+                if o.outcome == Outcome::Read {
+                    score.read += 1;
+                }
+            }
+        }
+    }
+
+    scores.retain(|s| s.attempts > 0);
+    scores
+}
+
+/// Runs played on one day.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DaySummary {
+    pub day: i64,
+    pub runs: usize,
+    pub words_read: usize,
+    pub best_words_per_minute: usize,
+    /// The hardest difficulty played that day.
+    pub difficulty: Difficulty,
+}
+
+/// Groups the run history by day, oldest first.
+pub fn runs_by_day(history: &[RunRecord]) -> Vec<DaySummary> {
+    let mut days: Vec<DaySummary> = vec![];
+    let mut runs: Vec<&RunRecord> = history.iter().collect();
+    runs.sort_by_key(|r| r.day);
+
+    for run in runs {
+        match days.last_mut() {
+            Some(summary) if summary.day == run.day => {
+                summary.runs += 1;
+                summary.words_read += run.words_read;
+                summary.best_words_per_minute = summary.best_words_per_minute.max(run.words_per_minute());
+                summary.difficulty = summary.difficulty.clone().max(run.difficulty.clone());
+            },
+            _ => days.push(DaySummary {
+                day: run.day,
+                runs: 1,
+                words_read: run.words_read,
+                best_words_per_minute: run.words_per_minute(),
+                difficulty: run.difficulty.clone(),
+            }),
+        }
+    }
+    days
+}
+
+/// Letters to switch on next, taken from `order` (the curriculum's teaching order, then the keyboard's).
+/// Nothing is suggested until every switched on letter with enough attempts is read alone most of the time.
+pub fn suggest_letters(mastery: &[Mastery], enabled: &[String], order: &[String], language: &Language) -> Vec<String> {
+    let enabled_scores: Vec<&Mastery> = mastery.iter().filter(|m| enabled.contains(&m.unit) && m.is_trusted()).collect();
+    if enabled_scores.is_empty() || enabled_scores.iter().any(|m| m.percent() < MASTERED_PERCENT) {
+        return vec![];
+    }
+
+    let mut suggestions: Vec<String> = vec![];
+    let keyboard_order = language.letter_groups.iter().map(|(label, _)| label.to_lowercase());
+    for letter in order.iter().cloned().chain(keyboard_order) {
+        if !enabled.contains(&letter) && !suggestions.contains(&letter) {
+            suggestions.push(letter);
+        }
+    }
+    suggestions.truncate(SUGGESTED_LETTERS);
+    suggestions
+}
+
+/// Shows a profile's progress: how well each letter and digraph is read, runs over time, and which letters to add next.
+#[component]
+pub fn stats_page() -> impl IntoView {
+    let profiles::StoredProfiles { profiles, current_profile, .. } = profiles::use_profiles();
+    let profile_id = current_profile.get_untracked().unwrap_or_default();
+    let profile_name = move || profiles.with(|profiles| profiles.iter().find(|p| p.id == profile_id).map(|p| p.name.clone()));
+
+    let (word_outcomes, _, _) = use_local_storage::<Vec<WordOutcome>, JsonCodec>(profiles::storage_key(profile_id, "word_outcomes"));
+    let (run_history, _, _) = use_local_storage::<Vec<RunRecord>, JsonCodec>(profiles::storage_key(profile_id, "run_history"));
+    let (allowed_chars, _, _) = use_local_storage::<String, JsonCodec>(profiles::storage_key(profile_id, "allowed_chars"));
+    let (all_words, _, _) = use_local_storage::<bool, JsonCodec>(profiles::storage_key(profile_id, "all_words"));
+    let (language_code, _, _) = use_local_storage::<String, JsonCodec>(profiles::storage_key(profile_id, "language"));
+    let language = move || languages::language(&language_code.get());
+
+    let locale = i18n::use_locale().locale;
+    let scores = create_memo(move |_| word_outcomes.with(|o| mastery(o, language())));
+
+    // the last lesson lists every letter in the order the curriculum teaches them
+    let lessons = create_local_resource(move || language().code.to_string(), |language| async move { curriculum::fetch_lessons(language).await });
+    let suggestions = move || {
+        let order = lessons.get().and_then(|l| l.ok()).and_then(|l| l.last().map(|lesson| lesson.letters.clone())).unwrap_or_default();
+        let enabled = language().letters_in(&allowed_chars.get());
+        scores.with(|scores| suggest_letters(scores, &enabled, &order, language()))
+    };
+
+    let mastery_row = move |m: Mastery| {
+        let percent = m.percent();
+        view! {
+            <div class="mastery" class:mastery-untrusted=!m.is_trusted()>
+                <span class="mastery-unit">{m.unit.clone()}</span>
+                <span class="mastery-bar"><span class="mastery-fill" style:width=format!("{}%", percent)></span></span>
+                <span>{move || i18n::mastery_text(locale.get(), percent, m.read, m.attempts)}</span>
+            </div>
+        }
+    };
+
+    let day_row = move |summary: DaySummary| view! {
+        <li>
+            {move || i18n::day_summary_text(locale.get(), &day_label(summary.day), summary.runs, summary.words_read, summary.best_words_per_minute)}
+            <img class="stats-difficulty" src=difficulty_assets(summary.difficulty) />
+        </li>
+    };
+
+    let suggestions_view = move || {
+        let suggested = suggestions();
+        match (all_words.get(), suggested.is_empty()) {
+            (true, _) => view! { <div>{Msg::ReadsAllLetters.text(locale.get())}</div> }.into_view(),
+            (false, true) => view! { <div>{Msg::KeepPractising.text(locale.get())}</div> }.into_view(),
+            (false, false) => view! { <div>{Msg::ReadyToAdd.text(locale.get())}<span style="font-weight: bold;">{suggested.join(", ")}</span></div> }.into_view(),
+        }
+    };
+
+    view! {
+        <h1 class="settings-title">{move || Msg::ProgressOf.text(locale.get())}{profile_name}</h1>
+        <div class="flex-center"><A href="/">{move || Msg::Back.text(locale.get())}</A></div>
+        <h2 class="stats-title">{move || Msg::Letters.text(locale.get())}</h2>
+        <div class="flex-center">
+            <div class="stats">
+                {move || match scores.with(|s| s.is_empty()) {
+                    true => view! { <div>{Msg::NoWordsReadYet.text(locale.get())}</div> }.into_view(),
+                    false => scores.get().into_iter().map(mastery_row).collect_view(),
+                }}
+            </div>
+        </div>
+        <h2 class="stats-title">{move || Msg::NextLetters.text(locale.get())}</h2>
+        <div class="flex-center"><div class="stats">{suggestions_view}</div></div>
+        <h2 class="stats-title">{move || Msg::DaysReading.text(locale.get())}</h2>
+        <div class="flex-center">
+            <ol class="leaderboard">{move || run_history.with(|h| runs_by_day(h)).into_iter().rev().map(day_row).collect_view()}</ol>
+        </div>
+    }
+}
+
+/// A local day number as a date, e.g. "18/10/2026".
+fn day_label(day: i64) -> String {
+    let date = js_sys::Date::new(&((day as f64) * 86_400_000.0).into());
+    format!("{}/{}/{}", date.get_utc_date(), date.get_utc_month() + 1, date.get_utc_full_year())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::languages::{ENGLISH, PORTUGUESE};
+    use crate::test_support::{outcome, run_on};
+
+    fn unit<'a>(scores: &'a [Mastery], unit: &str) -> Option<&'a Mastery> {
+        scores.iter().find(|m| m.unit == unit)
+    }
+
+    #[test]
+    fn test_mastery() {
+        let scores = mastery(&[outcome("palha", Outcome::Read, 0.0), outcome("Olha", Outcome::NeededHelp, 0.0), outcome("pá", Outcome::Read, 0.0)], &PORTUGUESE);
+
+        assert_eq!(unit(&scores, "a").map(|m| (m.read, m.attempts)), Some((2, 3)), "each word counts once per letter, accents included");
+        assert_eq!(unit(&scores, "lh").map(|m| m.percent()), Some(50));
+        assert_eq!(unit(&scores, "o").map(|m| m.percent()), Some(0));
+        assert_eq!(unit(&scores, "z"), None);
+        assert_eq!(scores.last().map(|m| m.unit.as_str()), Some("lh"), "digraphs come after the letters");
+
+        let english = |text: &str| WordOutcome { language: "en".to_string(), ..outcome(text, Outcome::Read, 0.0) };
+        let scores = mastery(&[english("ship"), english("pá"), outcome("chave", Outcome::Read, 0.0)], &ENGLISH);
+        assert_eq!(unit(&scores, "sh").map(|m| m.attempts), Some(1));
+        assert_eq!(unit(&scores, "a"), None, "accented letters are not on the English keyboard");
+        assert_eq!(unit(&scores, "c"), None, "words read in another language are left out");
+    }
+
+    #[test]
+    fn test_runs_by_day() {
+        let days = runs_by_day(&[run_on(3, 10, 60), run_on(1, 20, 60), RunRecord { difficulty: Difficulty::Medium, ..run_on(3, 30, 60) }]);
+
+        assert_eq!(days.len(), 2);
+        assert_eq!(days[0].day, 1);
+        assert_eq!((days[1].runs, days[1].words_read, days[1].best_words_per_minute, days[1].difficulty.clone()), (2, 40, 30, Difficulty::Medium));
+    }
+
+    #[test]
+    fn test_suggest_letters() {
+        let enabled: Vec<String> = ["a", "p"].iter().map(|l| l.to_string()).collect();
+        let order: Vec<String> = ["a", "p", "t", "l"].iter().map(|l| l.to_string()).collect();
+        let learnt = vec![Mastery { unit: "a".to_string(), read: 9, attempts: 10 }, Mastery { unit: "p".to_string(), read: 8, attempts: 10 }];
+        assert_eq!(suggest_letters(&learnt, &enabled, &order, &PORTUGUESE), vec!["t", "l"]);
+        assert_eq!(suggest_letters(&learnt, &enabled, &[], &PORTUGUESE), vec!["e", "i"], "without a curriculum, keyboard order");
+
+        let struggling = vec![Mastery { unit: "a".to_string(), read: 9, attempts: 10 }, Mastery { unit: "p".to_string(), read: 5, attempts: 10 }];
+        assert!(suggest_letters(&struggling, &enabled, &order, &PORTUGUESE).is_empty());
+
+        let too_few = vec![Mastery { unit: "a".to_string(), read: 3, attempts: 3 }];
+        assert!(suggest_letters(&too_few, &enabled, &order, &PORTUGUESE).is_empty());
+    }
+}
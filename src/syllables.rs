@@ -0,0 +1,212 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+const VOWELS: &str = "aeiouyàáâãèéêìíîòóôõùúû";
+
+/// Consonant pairs that are never split across syllables: "li-vro", "a-tle-ta", "com-pra".
+const CLUSTER_FIRSTS: &str = "pbtdcgfv";
+const CLUSTER_SECONDS: &str = "lr";
+
+/// Digraphs that are read as a single consonant and always start a syllable: "pa-lha", "ma-nhã", "pe-que-no".
+const CONSONANT_DIGRAPHS: [&str; 5] = ["lh", "nh", "ch", "qu", "gu"];
+
+fn lower_letters(word: &str) -> Vec<String> {
+    word.graphemes(true).map(|g| g.to_lowercase()).collect()
+}
+
+fn is_vowel(letter: &str) -> bool {
+    letter.chars().next().is_some_and(|c| VOWELS.contains(c))
+}
+
+/// Whether the letter at `i` is the "u" of "qu"/"gu" before a vowel, which is silent or a glide (quilo, água)
+/// and so belongs with the consonant before it.
+fn is_digraph_u(letters: &[String], i: usize) -> bool {
+    letters[i] == "u"
+        && i > 0
+        && (letters[i - 1] == "q" || letters[i - 1] == "g")
+        && letters.get(i + 1).is_some_and(|l| is_vowel(l))
+}
+
+/// Index ranges of the vowel nuclei of a single word, with falling diphthongs (pai, pau, mãe, pão) kept in one nucleus.
+fn nuclei(letters: &[String]) -> Vec<(usize, usize)> {
+    let mut nuclei: Vec<(usize, usize)> = vec![];
+
+    for (i, letter) in letters.iter().enumerate() {
+        if !is_vowel(letter) || is_digraph_u(letters, i) {
+            continue;
+        }
+        let extends_previous = match nuclei.last() {
+            Some((_, end)) if *end == i => {
+                let prev = letters[i - 1].as_str();
+                // "i"/"u" after a vowel is a glide, except before "nh" (ra-i-nha)
+                let glide = (letter == "i" || letter == "u")
+                    && !(letters.get(i + 1).map(|l| l.as_str()) == Some("n") && letters.get(i + 2).map(|l| l.as_str()) == Some("h"));
+                let nasal_diphthong = (letter == "o" || letter == "e") && (prev == "ã" || prev == "õ");
+                glide || nasal_diphthong
+            },
+            _ => false,
+        };
+        match extends_previous {
+            true => nuclei.last_mut().unwrap().1 = i + 1,
+            false => nuclei.push((i, i + 1)),
+        }
+    }
+
+    nuclei
+}
+
+/// Where the syllable boundary falls in the consonants between two nuclei, `letters[start..end]`.
+fn boundary(letters: &[String], start: usize, end: usize) -> usize {
+    // group the consonants into units, digraphs counting as one
+    let mut units: Vec<(usize, usize)> = vec![];
+    let mut i = start;
+    while i < end {
+        let len = match i + 1 < end && CONSONANT_DIGRAPHS.contains(&letters[i..i + 2].concat().as_str()) {
+            true => 2,
+            false => 1,
+        };
+        units.push((i, i + len));
+        i += len;
+    }
+
+    match units.len() {
+        0 => end,
+        1 => units[0].0,
+        n => {
+            let (first, second) = (&units[n - 2], &units[n - 1]);
+            let is_cluster = first.1 - first.0 == 1
+                && second.1 - second.0 == 1
+                && CLUSTER_FIRSTS.contains(letters[first.0].as_str())
+                && CLUSTER_SECONDS.contains(letters[second.0].as_str());
+            if is_cluster { first.0 } else { second.0 }
+        },
+    }
+}
+
+/// Splits a single run of letters into syllables.
+fn syllabify_letters(word: &str) -> Vec<String> {
+    let graphemes: Vec<&str> = word.graphemes(true).collect();
+    let letters = lower_letters(word);
+    let nuclei = nuclei(&letters);
+
+    if nuclei.len() < 2 {
+        return vec![word.to_string()];
+    }
+
+    let mut starts = vec![0];
+    for pair in nuclei.windows(2) {
+        starts.push(boundary(&letters, pair[0].1, pair[1].0));
+    }
+    starts.push(graphemes.len());
+
+    starts.windows(2).map(|w| graphemes[w[0]..w[1]].concat()).collect()
+}
+
+/// Splits a word (or a whole sentence) into syllables, following European/Brazilian Portuguese rules:
+/// "batata" becomes ["ba", "ta", "ta"]. Anything that is not a letter (spaces, hyphens, punctuation) stays glued
+/// to the syllable before it, so joining the syllables gives back the original text.
+pub fn syllabify(text: &str) -> Vec<String> {
+    fn flush_run(run: &mut String, syllables: &mut Vec<String>, pending_prefix: &mut String) {
+        if run.is_empty() {
+            return;
+        }
+        let mut run_syllables = syllabify_letters(run);
+        run_syllables[0].insert_str(0, pending_prefix);
+        pending_prefix.clear();
+        syllables.append(&mut run_syllables);
+        run.clear();
+    }
+
+    let mut syllables: Vec<String> = vec![];
+    let mut pending_prefix = String::new();
+    let mut run = String::new();
+
+    for g in text.graphemes(true) {
+        if g.chars().next().is_some_and(|c| c.is_alphabetic()) {
+            run.push_str(g);
+            continue;
+        }
+        flush_run(&mut run, &mut syllables, &mut pending_prefix);
+        match syllables.last_mut() {
+            Some(last) => last.push_str(g),
+            None => pending_prefix.push_str(g),
+        }
+    }
+    flush_run(&mut run, &mut syllables, &mut pending_prefix);
+
+    if !pending_prefix.is_empty() {
+        syllables.push(pending_prefix);
+    }
+    syllables
+}
+
+/// Number of syllables in a single word.
+pub fn syllable_count(word: &str) -> usize {
+    word.split(|c: char| !c.is_alphabetic())
+        .map(|run| nuclei(&lower_letters(run)).len())
+        .sum()
+}
+
+/// The rules a language's words are split into syllables with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Syllabifier {
+    /// The Portuguese rules of [`syllabify`] and [`syllable_count`].
+    Portuguese,
+}
+
+impl Syllabifier {
+    pub fn syllabify(self, text: &str) -> Vec<String> {
+        match self {
+            Syllabifier::Portuguese => syllabify(text),
+        }
+    }
+
+    pub fn syllable_count(self, word: &str) -> usize {
+        match self {
+            Syllabifier::Portuguese => syllable_count(word),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(word: &str) -> String {
+        syllabify(word).join("·")
+    }
+
+    #[test]
+    fn test_syllabify() {
+        for (word, expected) in [
+            ("batata", "ba·ta·ta"), ("pato", "pa·to"), ("ai", "ai"), ("pai", "pai"), ("sol", "sol"),
+            ("palhinha", "pa·lhi·nha"), ("manhã", "ma·nhã"), ("cachorro", "ca·chor·ro"), ("pássaro", "pás·sa·ro"),
+            ("livro", "li·vro"), ("atleta", "a·tle·ta"), ("compra", "com·pra"), ("parte", "par·te"), ("nascer", "nas·cer"),
+            ("inspirar", "ins·pi·rar"), ("abstrato", "abs·tra·to"), ("pequeno", "pe·que·no"), ("água", "á·gua"),
+            ("alguma", "al·gu·ma"), ("quilo", "qui·lo"), ("baía", "ba·í·a"), ("saúde", "sa·ú·de"), ("rainha", "ra·i·nha"),
+            ("piano", "pi·a·no"), ("leão", "le·ão"), ("mãe", "mãe"), ("peixe", "pei·xe"), ("muito", "mui·to"),
+            ("digno", "dig·no"), ("Outono", "Ou·to·no"), ("pô-los", "pô-·los"),
+        ] {
+            assert_eq!(split(word), expected, "{} should split as {}", word, expected);
+        }
+    }
+
+    #[test]
+    fn test_syllabify_sentence() {
+        let sentence = "O pato nada.";
+        let syllables = syllabify(sentence);
+
+        assert_eq!(syllables, vec!["O ", "pa", "to ", "na", "da."]);
+        assert_eq!(syllables.concat(), sentence);
+        assert_eq!(syllabify("«Olá!»").concat(), "«Olá!»");
+        assert_eq!(syllabify("24"), vec!["24"]);
+        assert!(syllabify("").is_empty());
+    }
+
+    #[test]
+    fn test_syllable_count() {
+        for (word, syllables) in [ ("ai", 1), ("pai", 1), ("papa", 2), ("tia", 2), ("mãe", 1), ("pão", 1), ("leão", 2), ("baía", 3), ("saúde", 3), ("rainha", 3),
+                                   ("quilo", 2), ("água", 2), ("guerra", 2), ("batata", 3), ("hipotético", 5), ("Outono", 3), ("pô-los", 2), ("24", 0) ] {
+            assert_eq!(syllable_count(word), syllables, "{} should have {} syllables", word, syllables);
+        }
+    }
+}
@@ -0,0 +1,43 @@
+//! Fixtures shared by the unit tests.
+
+use crate::app::{Difficulty, GameMode, ReadingMode};
+use crate::outcomes::{Outcome, WordOutcome};
+use crate::scores::RunRecord;
+
+/// The lines of a file, as the parsers get them.
+pub fn lines(lines: &[&str]) -> Vec<String> {
+    lines.iter().map(|l| l.to_string()).collect()
+}
+
+/// A classic run of easy words played on `day`.
+pub fn run_on(day: i64, words_read: usize, duration_secs: u32) -> RunRecord {
+    RunRecord {
+        game_mode: GameMode::Classic,
+        reading_mode: ReadingMode::Words,
+        difficulty: Difficulty::Easy,
+        allowed_chars: None,
+        words_read,
+        duration_secs,
+        day,
+        finished_at: 0.0,
+        language: String::new(),
+    }
+}
+
+/// Ten words read in a minute, told apart from other runs by when they finished.
+pub fn run_at(finished_at: f64) -> RunRecord {
+    RunRecord { finished_at, ..run_on(0, 10, 60) }
+}
+
+/// An easy word read in one and a half seconds.
+pub fn outcome(text: &str, outcome: Outcome, at: f64) -> WordOutcome {
+    WordOutcome {
+        text: text.to_string(),
+        reading_mode: ReadingMode::Words,
+        difficulty: Difficulty::Easy,
+        outcome,
+        duration_ms: 1500,
+        at,
+        language: String::new(),
+    }
+}
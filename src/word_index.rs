@@ -0,0 +1,215 @@
+use gloo_net::http::Request;
+use leptos::{logging, ServerFnError};
+use rand::thread_rng;
+use serde::{Serialize, Deserialize};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::rc::Rc;
+
+use crate::app::{Difficulty, PoolOptions, ReadingMode};
+use crate::curriculum::Lesson;
+use crate::languages::{self, Language};
+use crate::lexicanum::{self, PoolPreferences, PoolWord, WordPoolError, CHOICES_PER_QUESTION};
+
+/// Where the server publishes the index of a language, and where the service worker keeps a copy for offline use.
+/// The static csr build expects the files written by `--write-word-index` at the same place.
+pub fn word_index_path(code: &str) -> String {
+    format!("/word-index/{}.json", code)
+}
+
+/// The static csr build has no server to ask: words and lessons always come from the word index.
+pub const SERVERLESS: bool = cfg!(feature = "csr");
+
+/// Every playable word, sentence and lesson, so the browser can put pools together on its own when there is no server.
+/// Words are classified by the server, with the same rules as [`lexicanum`], and grouped by the difficulties they fit
+/// (a bit per entry of [`Difficulty::ALL`]) so each word is only written once.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct WordIndex {
+    /// Words by difficulty bit mask.
+    pub words: BTreeMap<u8, Vec<String>>,
+    pub sentences: Vec<String>,
+    #[serde(default)]
+    pub lessons: Vec<Lesson>,
+    /// Lowercase word to definition, from the server's definitions file.
+    #[serde(default)]
+    pub definitions: BTreeMap<String, String>,
+    /// Lowercase word to picture path under `assets/`, from the server's picture manifest.
+    #[serde(default)]
+    pub pictures: BTreeMap<String, String>,
+}
+
+fn difficulty_bit(diff: &Difficulty) -> u8 {
+    let position = Difficulty::ALL.iter().position(|d| d == diff).unwrap_or_default();
+    1 << position
+}
+
+impl WordIndex {
+    pub fn new(words: &HashMap<Difficulty, Vec<String>>, sentences: &[String], lessons: &[Lesson], definitions: &BTreeMap<String, String>, pictures: &BTreeMap<String, String>) -> Self {
+        let mut masks: BTreeMap<&str, u8> = BTreeMap::new();
+        for (diff, diff_words) in words {
+            for w in diff_words {
+                *masks.entry(w.as_str()).or_default() |= difficulty_bit(diff);
+            }
+        }
+
+        let mut grouped: BTreeMap<u8, Vec<String>> = BTreeMap::new();
+        for (w, mask) in masks {
+            grouped.entry(mask).or_default().push(w.to_string());
+        }
+
+        WordIndex {
+            words: grouped,
+            sentences: sentences.to_vec(),
+            lessons: lessons.to_vec(),
+            definitions: definitions.clone(),
+            pictures: pictures.clone(),
+        }
+    }
+
+    /// The words of each difficulty, as the server keeps them.
+    pub fn classified_words(&self) -> HashMap<Difficulty, Vec<String>> {
+        Difficulty::ALL
+            .iter()
+            .map(|diff| {
+                let bit = difficulty_bit(diff);
+                let diff_words = self.words
+                    .iter()
+                    .filter(|(mask, _)| *mask & bit != 0)
+                    .flat_map(|(_, words)| words.iter().cloned())
+                    .collect();
+                (diff.clone(), diff_words)
+            })
+            .collect()
+    }
+}
+
+/// The index of one language unpacked, ready to draw pools from.
+struct LocalWords {
+    language: &'static Language,
+    words: HashMap<Difficulty, Vec<String>>,
+    sentences: Vec<String>,
+    lessons: Vec<Lesson>,
+    definitions: BTreeMap<String, String>,
+    pictures: BTreeMap<String, String>,
+}
+
+thread_local! {
+    static LOCAL_WORDS: RefCell<HashMap<String, Rc<LocalWords>>> = RefCell::new(HashMap::new());
+}
+
+/// Fetches the index of a language once per page load. Offline, the service worker answers with its cached copy.
+async fn local_words(code: &str) -> Result<Rc<LocalWords>, WordPoolError> {
+    if let Some(local) = LOCAL_WORDS.with(|l| l.borrow().get(code).cloned()) {
+        return Ok(local);
+    }
+
+    let language = languages::find_language(code).ok_or(WordPoolError::UnknownLanguage)?;
+    let response = Request::get(&word_index_path(code))
+        .send()
+        .await
+        .map_err(|_| WordPoolError::WordlistNotLoaded)?;
+    if response.status() == 404 {
+        return Err(WordPoolError::UnknownLanguage);
+    }
+    let index: WordIndex = response
+        .json()
+        .await
+        .map_err(|_| WordPoolError::WordlistNotLoaded)?;
+    let local = Rc::new(LocalWords { language, words: index.classified_words(), sentences: index.sentences, lessons: index.lessons, definitions: index.definitions, pictures: index.pictures });
+    LOCAL_WORDS.with(|l| l.borrow_mut().insert(code.to_string(), local.clone()));
+    Ok(local)
+}
+
+/// Asks the server with `from_server`, falling back to `locally` when there is no server or it cannot be reached.
+/// Word pool errors coming from the server are kept: the server was reached and had no words to give.
+pub async fn with_local_fallback<T>(from_server: impl Future<Output = Result<T, ServerFnError>>, locally: impl Future<Output = Result<T, WordPoolError>>) -> Result<T, ServerFnError> {
+    if SERVERLESS {
+        return Ok(locally.await?);
+    }
+
+    match from_server.await {
+        Err(e) if WordPoolError::from_server_fn_error(&e).is_none() => {
+            logging::log!("server unreachable ({}), using the word index", e);
+            Ok(locally.await?)
+        },
+        result => result,
+    }
+}
+
+/// Draws a pool in the browser, for when the server cannot be reached.
+pub async fn local_pool(options: PoolOptions) -> Result<Vec<PoolWord>, WordPoolError> {
+    let local = local_words(&options.language).await?;
+    match options.reading_mode {
+        ReadingMode::Words | ReadingMode::Spelling | ReadingMode::Recognition => {
+            let words = local.words.get(&options.difficulty).ok_or(WordPoolError::UnknownDifficulty)?;
+            let preferences = PoolPreferences { focus: &options.focus, review: &options.review, pictures: options.with_pictures.then_some(&local.pictures) };
+            match options.reading_mode {
+                ReadingMode::Recognition => lexicanum::question_pool(local.language, words, options.allowed_chars, options.num_words, CHOICES_PER_QUESTION, &preferences, &mut thread_rng()),
+                _ => lexicanum::word_pool(local.language, words, options.allowed_chars, options.num_words, &preferences, &mut thread_rng()),
+            }
+        },
+        ReadingMode::Sentences => lexicanum::sentence_pool(local.language, &local.sentences, options.allowed_chars, options.num_words, &options.difficulty, &options.review, &mut thread_rng()),
+    }
+}
+
+/// Counts matching words in the browser, for when the server cannot be reached.
+pub async fn local_count(language: String, allowed_chars: Option<String>, diff: Difficulty, reading_mode: ReadingMode) -> Result<usize, WordPoolError> {
+    let local = local_words(&language).await?;
+    let words = local.words.get(&diff).ok_or(WordPoolError::UnknownDifficulty)?;
+    lexicanum::matching_count(local.language, words, &local.sentences, allowed_chars, &diff, &reading_mode)
+}
+
+/// The lessons shipped with the index, for when the server cannot be reached.
+pub async fn local_lessons(language: String) -> Result<Vec<Lesson>, WordPoolError> {
+    Ok(local_words(&language).await?.lessons.clone())
+}
+
+/// A definition from the index, for when the server cannot be reached.
+pub async fn local_definition(language: String, word: String) -> Result<Option<String>, WordPoolError> {
+    Ok(local_words(&language).await?.definitions.get(&word.to_lowercase()).cloned())
+}
+
+/// The languages to offer when the server cannot be asked: every language the app knows, since only the ones
+/// with a word index will actually play.
+pub async fn local_languages() -> Result<Vec<String>, WordPoolError> {
+    Ok(languages::LANGUAGES.iter().map(|l| l.code.to_string()).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(words: &[&str]) -> Vec<String> {
+        words.iter().map(|w| w.to_string()).collect()
+    }
+
+    #[test]
+    fn test_word_index() {
+        let classified = HashMap::from([
+            (Difficulty::Easiest, words(&["pato", "tia"])),
+            (Difficulty::Easy, words(&["pato", "chá"])),
+            (Difficulty::Hardest, words(&["guarda-chuva", "bom dia"])),
+        ]);
+        let definitions = BTreeMap::from([("pato".to_string(), "Ave que nada.".to_string())]);
+        let pictures = BTreeMap::from([("pato".to_string(), "pictures/pato.svg".to_string())]);
+        let index = WordIndex::new(&classified, &words(&["O pato nada."]), &[], &definitions, &pictures);
+
+        assert_eq!(index.words.get(&0b11), Some(&words(&["pato"])), "a word fitting two difficulties is written once");
+        assert_eq!(index.words.get(&0b01), Some(&words(&["tia"])));
+        assert_eq!(index.words.get(&0b10000), Some(&words(&["bom dia", "guarda-chuva"])), "entries with a space stay whole");
+
+        let json = serde_json::to_string(&index).unwrap();
+        let unpacked = serde_json::from_str::<WordIndex>(&json).unwrap();
+        assert_eq!(unpacked.definitions, definitions);
+        assert_eq!(unpacked.pictures, pictures);
+        let unpacked = unpacked.classified_words();
+        for diff in Difficulty::ALL.iter() {
+            let mut expected = classified.get(diff).cloned().unwrap_or_default();
+            let mut actual = unpacked.get(diff).cloned().unwrap_or_default();
+            expected.sort();
+            actual.sort();
+            assert_eq!(actual, expected);
+        }
+    }
+}
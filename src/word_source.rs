@@ -0,0 +1,151 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{prelude::*, BufReader};
+
+/// Somewhere the server can get its raw list of words from, before they get sorted into difficulties.
+#[allow(async_fn_in_trait)]
+pub trait WordSource {
+    /// Where the words come from, for logs and error messages.
+    fn describe(&self) -> String;
+
+    async fn fetch_words(&self) -> Result<Vec<String>, WordSourceError>;
+}
+
+#[derive(Debug)]
+pub enum WordSourceError {
+    Io { path: String, error: std::io::Error },
+    Http { url: String, error: reqwest::Error },
+}
+
+impl fmt::Display for WordSourceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WordSourceError::Io { path, error } => write!(f, "couldnt read words from file {}: {}", path, error),
+            WordSourceError::Http { url, error } => write!(f, "couldnt fetch words from {}: {}", url, error),
+        }
+    }
+}
+
+impl std::error::Error for WordSourceError {}
+
+/// Plain text file with one word (or sentence) per line. Blank lines and lines starting with `#` are skipped.
+pub struct TextFileSource {
+    path: String,
+}
+
+impl TextFileSource {
+    pub fn new(path: impl Into<String>) -> Self {
+        TextFileSource { path: path.into() }
+    }
+
+    /// Every line of the file, trimmed, blank lines and comments included, for parsers that report errors by line number.
+    pub async fn fetch_lines(&self) -> Result<Vec<String>, WordSourceError> {
+        let to_error = |error| WordSourceError::Io { path: self.path.clone(), error };
+        let reader = BufReader::new(File::open(&self.path).map_err(to_error)?);
+
+        let mut lines = vec![];
+        for l in reader.lines() {
+            lines.push(l.map_err(to_error)?.trim().to_string());
+        }
+        Ok(lines)
+    }
+}
+
+impl WordSource for TextFileSource {
+    fn describe(&self) -> String {
+        format!("file {}", self.path)
+    }
+
+    async fn fetch_words(&self) -> Result<Vec<String>, WordSourceError> {
+        let lines = self.fetch_lines().await?;
+        Ok(lines.into_iter().filter(|l| !l.is_empty() && !l.starts_with('#')).collect())
+    }
+}
+
+/// HTTP endpoint answering a GET with a JSON array of words, e.g. `["pato", "bola"]`.
+pub struct HttpJsonSource {
+    url: String,
+}
+
+impl HttpJsonSource {
+    pub fn new(url: impl Into<String>) -> Self {
+        HttpJsonSource { url: url.into() }
+    }
+}
+
+impl WordSource for HttpJsonSource {
+    fn describe(&self) -> String {
+        format!("url {}", self.url)
+    }
+
+    async fn fetch_words(&self) -> Result<Vec<String>, WordSourceError> {
+        let to_error = |error| WordSourceError::Http { url: self.url.clone(), error };
+
+        let words: Vec<String> = reqwest::get(self.url.as_str())
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(to_error)?
+            .json()
+            .await
+            .map_err(to_error)?;
+
+        Ok(words
+            .into_iter()
+            .map(|w| w.trim().to_string())
+            .filter(|w| !w.is_empty())
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::net::TcpListener;
+
+    /// Serves a single canned HTTP response on a random local port and returns its url.
+    fn mock_server(status: &'static str, body: &'static str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0u8; 1024];
+            let _ = stream.read(&mut request);
+            let response = format!("HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", status, body.len(), body);
+            stream.write_all(response.as_bytes()).unwrap();
+        });
+
+        format!("http://{}/words", addr)
+    }
+
+    #[actix_web::test]
+    async fn test_http_json_source() {
+        let url = mock_server("200 OK", r#"["pato", " bola ", "", "pá"]"#);
+        let words = HttpJsonSource::new(url).fetch_words().await.unwrap();
+
+        assert_eq!(words, vec!["pato", "bola", "pá"]);
+    }
+
+    #[actix_web::test]
+    async fn test_http_json_source_errors() {
+        let url = mock_server("500 Internal Server Error", "[]");
+        assert!(matches!(HttpJsonSource::new(url).fetch_words().await, Err(WordSourceError::Http { .. })));
+
+        let url = mock_server("200 OK", r#"{"words": "not a list"}"#);
+        assert!(matches!(HttpJsonSource::new(url).fetch_words().await, Err(WordSourceError::Http { .. })));
+    }
+
+    #[actix_web::test]
+    async fn test_text_file_source() {
+        let path = std::env::temp_dir().join("learn-to-read-test-words.txt");
+        std::fs::write(&path, "# comment\npato\n\n  bola  \n").unwrap();
+
+        let words = TextFileSource::new(path.to_string_lossy()).fetch_words().await.unwrap();
+        assert_eq!(words, vec!["pato", "bola"]);
+        let lines = TextFileSource::new(path.to_string_lossy()).fetch_lines().await.unwrap();
+        assert_eq!(lines, vec!["# comment", "pato", "", "bola"]);
+
+        assert!(matches!(TextFileSource::new("does/not/exist.txt").fetch_words().await, Err(WordSourceError::Io { .. })));
+    }
+}